@@ -0,0 +1,136 @@
+//! Drawing trails into a persistent texture rather than from stored points.
+//!
+//! Each step, the texture is dimmed a little and every body draws a line from
+//! where it was before into it, so trails fade out smoothly and can be any
+//! length, while drawing them costs the same however long they are.
+
+use std::collections::{HashMap, HashSet};
+
+use macroquad::prelude::*;
+
+use three_bodies::World;
+
+/// The least the texture is dimmed by on a step, as an 8-bit texture cannot
+/// fade out by less without leaving faint trails behind for good.
+const MIN_DIM: f32 = 0.02;
+
+/// Trails accumulated in a texture the size of the screen.
+pub struct Accumulation {
+    target: RenderTarget,
+    /// The size of the screen the texture was made for.
+    size: Vec2,
+    /// The step of the simulation last drawn.
+    steps: u32,
+    /// Where each body was when last drawn.
+    positions: HashMap<usize, Vec2>,
+}
+
+impl Accumulation {
+    /// Creates an empty texture the size of the screen.
+    pub fn new() -> Self {
+        let size = vec2(screen_width(), screen_height());
+        let target = render_target(size.x as u32, size.y as u32);
+        target.texture.set_filter(FilterMode::Linear);
+        let accumulation = Self {
+            target,
+            size,
+            steps: 0,
+            positions: HashMap::new(),
+        };
+        accumulation.clear();
+        accumulation
+    }
+
+    /// Clears the texture.
+    fn clear(&self) {
+        set_camera(&self.screen_camera());
+        clear_background(BLACK);
+    }
+
+    /// Returns a camera drawing onto the texture in screen coordinates.
+    fn screen_camera(&self) -> Camera2D {
+        Camera2D {
+            render_target: Some(self.target.clone()),
+            ..Camera2D::from_display_rect(Rect::new(0.0, 0.0, self.size.x, self.size.y))
+        }
+    }
+
+    /// Dims the texture for every step taken since the last update, and
+    /// draws the movement of the bodies since then into it, as seen through
+    /// the camera.
+    ///
+    /// The texture starts over when the screen is resized or the simulation
+    /// restarts, and is left alone while trails are frozen.
+    pub fn update(&mut self, world: &World, camera: &Camera2D, hidden: &HashSet<usize>) {
+        let restarted = world.steps() < self.steps;
+        if vec2(screen_width(), screen_height()) != self.size {
+            *self = Self::new();
+        } else if restarted {
+            self.clear();
+        }
+        if restarted {
+            self.positions.clear();
+        }
+        let steps = world.steps() - self.steps.min(world.steps());
+        self.steps = world.steps();
+        if steps == 0 || world.config.freeze_trails {
+            return;
+        }
+
+        set_camera(&self.screen_camera());
+        let dim = (1.0 - world.config.trail_fade.powi(steps as i32)).max(MIN_DIM);
+        draw_rectangle(
+            0.0,
+            0.0,
+            self.size.x,
+            self.size.y,
+            Color::new(0.0, 0.0, 0.0, dim),
+        );
+
+        set_camera(&Camera2D {
+            render_target: Some(self.target.clone()),
+            ..*camera
+        });
+        for body in world
+            .bodies
+            .iter()
+            .filter(|body| !hidden.contains(&body.id))
+        {
+            let Some(last) = self.positions.insert(body.id, body.position) else {
+                continue;
+            };
+            // Don't draw a line all the way across the world where the body
+            // wrapped around it.
+            let delta = body.position - last;
+            if delta.x.abs() > world.config.world.x / 2.0
+                || delta.y.abs() > world.config.world.y / 2.0
+            {
+                continue;
+            }
+            draw_line(
+                last.x,
+                last.y,
+                body.position.x,
+                body.position.y,
+                2.0,
+                body.colour,
+            );
+        }
+    }
+
+    /// Draws the texture over the whole screen.
+    pub fn draw(&self) {
+        draw_texture_ex(
+            &self.target.texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(self.size),
+                // Render targets are upside down.
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+    }
+}
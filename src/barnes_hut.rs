@@ -0,0 +1,162 @@
+//! The Barnes-Hut approximation of gravity between many bodies.
+//!
+//! Bodies are sorted into a quadtree, where each node knows the total mass and
+//! centre of mass of the bodies within it. Nodes which are small compared to
+//! their distance from a body, as judged by the opening angle `theta`, act on
+//! it as a single body at their centre of mass, which brings the work for `n`
+//! bodies down from `n^2` to about `n log n`.
+
+use macroquad::prelude::*;
+
+use crate::{gravity, wrap_delta, Body, SimConfig};
+
+/// How deep the tree gets before bodies share a node, which only happens if
+/// they are practically on top of each other.
+const MAX_DEPTH: u32 = 32;
+
+/// Returns the approximate gravitational acceleration of every body.
+pub fn accelerations(bodies: &[Body], config: &SimConfig) -> Vec<Vec2> {
+    let tree = QuadTree::new(bodies);
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(idx, body)| {
+            if body.fixed {
+                Vec2::ZERO
+            } else {
+                tree.force(0, idx, bodies, config) * config.g / body.mass
+            }
+        })
+        .collect()
+}
+
+/// A quadtree over bodies, with the root at index 0.
+struct QuadTree {
+    nodes: Vec<Node>,
+}
+
+/// A square area of the world.
+struct Node {
+    /// The corner with the smallest coordinates.
+    min: Vec2,
+    /// The length of the sides.
+    size: f32,
+    /// The total mass of the bodies within.
+    mass: f32,
+    /// The sum of the positions of the bodies within, weighted by mass.
+    moment: Vec2,
+    /// The indices of the bodies within, if this is a leaf.
+    bodies: Vec<usize>,
+    /// The index of the first of the four children, if this is not a leaf.
+    children: Option<usize>,
+}
+
+impl Node {
+    fn new(min: Vec2, size: f32) -> Self {
+        Self {
+            min,
+            size,
+            mass: 0.0,
+            moment: Vec2::ZERO,
+            bodies: Vec::new(),
+            children: None,
+        }
+    }
+
+    /// Returns true if a position is within this node.
+    fn contains(&self, position: Vec2) -> bool {
+        position.cmpge(self.min).all() && position.cmple(self.min + self.size).all()
+    }
+
+    /// Returns which of the four children a position falls into.
+    fn quadrant(&self, position: Vec2) -> usize {
+        let half = self.min + self.size / 2.0;
+        usize::from(position.x >= half.x) + 2 * usize::from(position.y >= half.y)
+    }
+}
+
+impl QuadTree {
+    /// Builds a tree over all bodies, within the smallest square containing
+    /// them.
+    fn new(bodies: &[Body]) -> Self {
+        let min = bodies.iter().fold(Vec2::splat(f32::INFINITY), |min, body| {
+            min.min(body.position)
+        });
+        let max = bodies
+            .iter()
+            .fold(Vec2::splat(f32::NEG_INFINITY), |max, body| {
+                max.max(body.position)
+            });
+        let size = (max - min).max_element().max(1.0);
+        let mut tree = Self {
+            nodes: vec![Node::new(min, size)],
+        };
+        for idx in 0..bodies.len() {
+            tree.insert(0, idx, bodies, 0);
+        }
+        tree
+    }
+
+    /// Inserts a body into a node, splitting leaves which already hold one.
+    fn insert(&mut self, node: usize, idx: usize, bodies: &[Body], depth: u32) {
+        let body = &bodies[idx];
+        self.nodes[node].mass += body.mass;
+        self.nodes[node].moment += body.mass * body.position;
+
+        if let Some(first) = self.nodes[node].children {
+            let child = first + self.nodes[node].quadrant(body.position);
+            self.insert(child, idx, bodies, depth + 1);
+            return;
+        }
+        if self.nodes[node].bodies.is_empty() || depth >= MAX_DEPTH {
+            self.nodes[node].bodies.push(idx);
+            return;
+        }
+
+        // Split the leaf, and move its bodies down into the children. Their
+        // mass is already accounted for here, but not in the children.
+        let (min, half) = (self.nodes[node].min, self.nodes[node].size / 2.0);
+        let first = self.nodes.len();
+        for quadrant in 0..4 {
+            let offset = vec2((quadrant % 2) as f32, (quadrant / 2) as f32) * half;
+            self.nodes.push(Node::new(min + offset, half));
+        }
+        self.nodes[node].children = Some(first);
+        for other in std::mem::take(&mut self.nodes[node].bodies)
+            .into_iter()
+            .chain([idx])
+        {
+            let child = first + self.nodes[node].quadrant(bodies[other].position);
+            self.insert(child, other, bodies, depth + 1);
+        }
+    }
+
+    /// Returns the gravitational force a node applies to a body, without the
+    /// gravitational constant.
+    fn force(&self, node: usize, idx: usize, bodies: &[Body], config: &SimConfig) -> Vec2 {
+        let node = &self.nodes[node];
+        let body = &bodies[idx];
+        if node.mass <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let Some(first) = node.children else {
+            return node
+                .bodies
+                .iter()
+                .filter(|&&other| other != idx)
+                .map(|&other| body.force_from(&bodies[other], config))
+                .fold(Vec2::ZERO, |acc, force| acc + force);
+        };
+
+        // Far away nodes act as a single body, as long as they do not include
+        // the body itself.
+        let delta = wrap_delta(node.moment / node.mass - body.position, config);
+        if !node.contains(body.position) && node.size < config.theta * delta.length() {
+            // Groups of bodies never overlap the body, as they are far away.
+            return gravity(delta, body.mass, node.mass, 0.0, config);
+        }
+        (first..first + 4)
+            .map(|child| self.force(child, idx, bodies, config))
+            .fold(Vec2::ZERO, |acc, force| acc + force)
+    }
+}
@@ -0,0 +1,1765 @@
+//! A gravitational n-body simulation.
+//!
+//! A [`World`] holds the bodies, the trails they leave behind, and the
+//! configuration of the simulation. Nothing here draws to or reads from the
+//! screen, and the size of the world is part of the configuration, so the
+//! simulation runs just as well without a window.
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use rng::Rng;
+use units::Units;
+
+pub mod barnes_hut;
+pub mod lzw;
+pub mod presets;
+pub mod rng;
+pub mod scenario;
+pub mod search;
+pub mod settings;
+pub mod trajectory;
+pub mod units;
+
+#[cfg(target_arch = "wasm32")]
+pub const IS_WASM: bool = true;
+#[cfg(not(target_arch = "wasm32"))]
+pub const IS_WASM: bool = false;
+
+/// The fewest bodies for which the Barnes-Hut approximation is used, as the
+/// exact computation is faster and more accurate for fewer.
+pub const BARNES_HUT_MIN_BODIES: usize = 64;
+
+/// The most sub-steps a step is split into with adaptive time steps.
+pub const MAX_SUBSTEPS: u32 = 64;
+
+/// The most times colliding pairs are swept each step, for clusters of
+/// bodies which keep bouncing each other back into their neighbours.
+const MAX_COLLISION_PASSES: usize = 8;
+
+/// The opacity below which trail points are removed.
+pub const MIN_TRAIL_ALPHA: f32 = 0.01;
+
+/// How many frames a collision flash lasts.
+pub const EFFECT_FRAMES: u32 = 30;
+
+/// How many steps a body has to stay unbound in a wrapping world to count as
+/// escaped.
+pub const WRAP_EJECTION_STEPS: u32 = 300;
+
+/// The colours of bodies by id with the by-id palette, repeating for ids
+/// past the end.
+const ID_COLOURS: [Color; 8] = [RED, GREEN, BLUE, YELLOW, MAGENTA, ORANGE, SKYBLUE, PINK];
+
+/// A simulation, with its bodies, the trails they leave, and its
+/// configuration.
+pub struct World {
+    pub bodies: Vec<Body>,
+    pub trails: VecDeque<Trail>,
+    /// Flashes where bodies collided recently.
+    pub effects: Vec<Effect>,
+    pub config: SimConfig,
+    /// The bodies the current scenario started with, for replaying it.
+    pub initial_bodies: Vec<Body>,
+    /// Whether the simulation stopped because of a collision.
+    collided: bool,
+    /// The id of the first body which escaped the others, if any.
+    ejected: Option<usize>,
+    /// Watches for a body escaping, until one did.
+    ejection: Ejection,
+    /// How many collisions happened since the scenario started.
+    collisions: u32,
+    /// The smallest distance between any two bodies since the scenario
+    /// started.
+    min_distance: f32,
+    /// How many steps were taken since the scenario started.
+    steps: u32,
+    /// How many sub-steps the last step was split into.
+    substeps: u32,
+    /// Whether the bodies moved away from their initial state since they last
+    /// returned to it.
+    departed: bool,
+    /// The step at which the bodies last returned to their initial state.
+    last_return: u32,
+    /// How many steps it took the bodies to return to their initial state the
+    /// last time they did.
+    period: Option<u32>,
+    /// The kinetic energy lost in inelastic collisions and merges since the
+    /// scenario started.
+    dissipated: f32,
+    /// The id the next new body gets, which only ever grows, so ids are never
+    /// reused while trails of earlier bodies remain.
+    next_id: usize,
+}
+
+impl World {
+    /// Creates a world with some bodies and no trails yet.
+    pub fn new(bodies: Vec<Body>, config: SimConfig) -> Self {
+        let mut world = Self {
+            bodies: Vec::new(),
+            trails: VecDeque::new(),
+            effects: Vec::new(),
+            config,
+            initial_bodies: bodies,
+            collided: false,
+            ejected: None,
+            ejection: Ejection::default(),
+            collisions: 0,
+            min_distance: f32::INFINITY,
+            steps: 0,
+            substeps: 1,
+            departed: false,
+            last_return: 0,
+            period: None,
+            dissipated: 0.0,
+            next_id: 0,
+        };
+        world.reset();
+        world
+    }
+
+    /// Starts a new scenario with some bodies.
+    pub fn load(&mut self, bodies: Vec<Body>) {
+        self.initial_bodies = bodies;
+        self.reset();
+    }
+
+    /// Ages collision flashes by one frame, removing those which are over.
+    ///
+    /// Flashes age with frames rather than steps, so they also fade out
+    /// while the simulation is paused or stopped.
+    pub fn age_effects(&mut self) {
+        self.effects.iter_mut().for_each(|effect| effect.age += 1);
+        self.effects.retain(|effect| effect.age < EFFECT_FRAMES);
+    }
+
+    /// Returns a new id, different from those of all bodies so far.
+    pub fn new_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    /// Removes a body from the simulation, along with its trails if they
+    /// should not fade out by themselves.
+    pub fn remove_body(&mut self, id: usize, clear_trails: bool) {
+        self.bodies.retain(|body| body.id != id);
+        if clear_trails {
+            self.trails.retain(|trail| trail.body_id != id);
+        }
+    }
+
+    /// Restarts the current scenario from the beginning.
+    pub fn reset(&mut self) {
+        self.bodies = self.initial_bodies.clone();
+        self.trails.clear();
+        self.effects.clear();
+        self.collided = false;
+        self.ejected = None;
+        self.ejection = Ejection::default();
+        self.collisions = 0;
+        self.dissipated = 0.0;
+        self.min_distance = min_pairwise_distance(&self.bodies, &self.config);
+        self.steps = 0;
+        self.substeps = 1;
+        self.departed = false;
+        self.last_return = 0;
+        self.period = None;
+        let max_id = self.bodies.iter().map(|body| body.id + 1).max();
+        self.next_id = self.next_id.max(max_id.unwrap_or(0));
+    }
+
+    /// Changes the size of the world, scaling the positions of all bodies and
+    /// trails to match, so they stay within it.
+    ///
+    /// Empty sizes, such as those of a minimised window, are ignored.
+    pub fn resize(&mut self, size: Vec2) {
+        if size == self.config.world || size.min_element() <= 0.0 {
+            return;
+        }
+        let scale = size / self.config.world;
+        self.config.world = size;
+        for body in self.bodies.iter_mut().chain(&mut self.initial_bodies) {
+            body.position *= scale;
+            body.apply_boundary(&self.config);
+        }
+        for trail in self.trails.iter_mut() {
+            trail.position *= scale;
+        }
+    }
+
+    /// Returns true if the simulation stopped because of a collision.
+    pub fn collided(&self) -> bool {
+        self.collided
+    }
+
+    /// Returns the id of the first body which escaped the others since the
+    /// scenario started, if any.
+    pub fn ejected(&self) -> Option<usize> {
+        self.ejected
+    }
+
+    /// Returns true if the simulation stopped, either because of a collision,
+    /// or because a body escaped and the simulation is configured to stop
+    /// then.
+    pub fn stopped(&self) -> bool {
+        self.collided || (self.ejected.is_some() && self.config.stop_on_ejection)
+    }
+
+    /// Returns how many collisions happened since the scenario started.
+    pub fn collisions(&self) -> u32 {
+        self.collisions
+    }
+
+    /// Returns the smallest distance between any two bodies since the
+    /// scenario started.
+    pub fn min_distance(&self) -> f32 {
+        self.min_distance
+    }
+
+    /// Returns how many steps were taken since the scenario started.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Returns the kinetic energy lost in inelastic collisions and merges
+    /// since the scenario started.
+    pub fn dissipated(&self) -> f32 {
+        self.dissipated
+    }
+
+    /// Returns how many sub-steps the last step was split into.
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    /// Returns how many steps it took the bodies to return to their initial
+    /// state the last time they did, if they did.
+    pub fn period(&self) -> Option<u32> {
+        self.period
+    }
+
+    /// Returns how many steps ago the bodies last returned to their initial
+    /// state.
+    pub fn steps_since_return(&self) -> u32 {
+        self.steps - self.last_return
+    }
+
+    /// Advances the world by one step, leaving trails behind, unless it has
+    /// stopped.
+    pub fn step(&mut self, dt: f32) {
+        if self.stopped() {
+            return;
+        }
+        leave_trails(&self.bodies, &mut self.trails, &self.config);
+
+        // Elastic collisions are resolved against the positions at the start
+        // of the step, every merge removes one body, and bodies passing
+        // through each other collide when they start overlapping.
+        let colliding = has_collision(&self.bodies, &self.config);
+        let count = self.bodies.len();
+        let previous = self.bodies.clone();
+        self.substeps = substeps(&self.bodies, &self.config);
+        self.collided = !step_dissipating(
+            &mut self.bodies,
+            &self.config,
+            dt,
+            self.substeps,
+            &mut self.dissipated,
+        );
+        self.collisions += match self.config.collision_mode {
+            CollisionMode::Stop => u32::from(self.collided),
+            CollisionMode::Elastic => u32::from(colliding),
+            CollisionMode::Merge => (count - self.bodies.len()) as u32,
+            CollisionMode::PassThrough => {
+                u32::from(!colliding && has_collision(&self.bodies, &self.config))
+            }
+        };
+        self.effects
+            .extend(collision_points(&previous, &self.bodies, &self.config));
+        self.min_distance = self
+            .min_distance
+            .min(min_pairwise_distance(&self.bodies, &self.config));
+
+        if self.ejected.is_none() {
+            self.ejected = self
+                .ejection
+                .update(&self.bodies, &self.config)
+                .map(|idx| self.bodies[idx].id);
+        }
+
+        // The bodies only return to their initial state after leaving it, as
+        // they stay close to it for a while after starting or returning.
+        self.steps += 1;
+        if let Some(distance) = state_distance(&self.initial_bodies, &self.bodies, &self.config) {
+            if distance > self.config.period_tolerance {
+                self.departed = true;
+            } else if self.departed {
+                self.departed = false;
+                self.period = Some(self.steps - self.last_return);
+                self.last_return = self.steps;
+            }
+        }
+    }
+}
+
+/// Leaves trails at the current positions of all bodies, fading out older
+/// ones, and dropping the oldest ones beyond the maximum number of trails.
+///
+/// Frozen trails are left exactly as they are.
+pub fn leave_trails(bodies: &[Body], trails: &mut VecDeque<Trail>, config: &SimConfig) {
+    if config.freeze_trails {
+        return;
+    }
+    trails
+        .iter_mut()
+        .for_each(|trail| trail.colour.a *= config.trail_fade);
+    trails.extend(bodies.iter().map(|body| Trail::new(body, bodies, config)));
+    while trails
+        .front()
+        .is_some_and(|trail| trail.colour.a < MIN_TRAIL_ALPHA || trails.len() > config.max_trails)
+    {
+        trails.pop_front();
+    }
+}
+
+/// Advances the simulation by one step, split into sub-steps if adaptive
+/// time steps are on.
+///
+/// Returns false if the simulation should stop because of a collision.
+pub fn step(bodies: &mut Vec<Body>, config: &SimConfig, dt: f32) -> bool {
+    let substeps = substeps(bodies, config);
+    step_dissipating(bodies, config, dt, substeps, &mut 0.0)
+}
+
+/// Advances the simulation by one step like [`step`], split into a given
+/// number of sub-steps, adding the kinetic energy lost in collisions to
+/// `dissipated`.
+fn step_dissipating(
+    bodies: &mut Vec<Body>,
+    config: &SimConfig,
+    dt: f32,
+    substeps: u32,
+    dissipated: &mut f32,
+) -> bool {
+    for _ in 0..substeps {
+        if !substep(bodies, config, dt / substeps as f32, dissipated) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns how many sub-steps the next step is split into.
+///
+/// With adaptive time steps, steps are split into more sub-steps the closer
+/// the closest two bodies are, as forces change quickly during close
+/// encounters.
+pub fn substeps(bodies: &[Body], config: &SimConfig) -> u32 {
+    if !config.adaptive {
+        return 1;
+    }
+    let distance = min_pairwise_distance(bodies, config);
+    ((config.adaptive_distance / distance).ceil() as u32).clamp(1, MAX_SUBSTEPS)
+}
+
+/// Advances the simulation by one sub-step, adding the kinetic energy lost in
+/// collisions to `dissipated`.
+///
+/// Returns false if the simulation should stop because of a collision.
+fn substep(bodies: &mut Vec<Body>, config: &SimConfig, dt: f32, dissipated: &mut f32) -> bool {
+    match config.integrator {
+        Integrator::Euler => {
+            // Calculate forces to apply based on last step's positions.
+            let accelerations = accelerations(bodies, config);
+            let kinetic = kinetic_energy(bodies);
+            let collided = bounce_collisions(bodies, config);
+            *dissipated += kinetic - kinetic_energy(bodies);
+            for ((body, acceleration), collided) in
+                bodies.iter_mut().zip(accelerations).zip(collided)
+            {
+                if !collided {
+                    body.update_velocity(acceleration, dt);
+                }
+            }
+
+            // Update positions based on new velocities.
+            bodies
+                .iter_mut()
+                .for_each(|body| body.update_position(dt, config));
+        }
+        Integrator::Verlet => *dissipated += verlet_step(bodies, config, dt),
+        Integrator::RK4 => *dissipated += rk4_step(bodies, config, dt),
+    }
+
+    match config.collision_mode {
+        // If two bodies collide, stop the simulation.
+        CollisionMode::Stop => return !has_collision(bodies, config),
+        CollisionMode::Elastic | CollisionMode::PassThrough => {}
+        CollisionMode::Merge => *dissipated += merge_collisions(bodies, config),
+    }
+    true
+}
+
+/// Returns the acceleration of every body, from gravity and, if any, from
+/// damping.
+///
+/// Fixed bodies do not accelerate, so every integrator leaves them in place.
+pub fn accelerations(bodies: &[Body], config: &SimConfig) -> Vec<Vec2> {
+    let mut accelerations = gravitational_accelerations(bodies, config);
+    if config.damping > 0.0 {
+        for (acceleration, body) in accelerations.iter_mut().zip(bodies) {
+            if !body.fixed {
+                *acceleration -= config.damping * body.velocity;
+            }
+        }
+    }
+    accelerations
+}
+
+/// Returns the gravitational acceleration of every body, computing the force
+/// between each pair of bodies only once.
+///
+/// The force of each pair is applied to both bodies in opposite directions,
+/// so the forces cancel out exactly and momentum is conserved. For many
+/// bodies, this can be approximated using Barnes-Hut instead.
+fn gravitational_accelerations(bodies: &[Body], config: &SimConfig) -> Vec<Vec2> {
+    if config.barnes_hut && bodies.len() >= BARNES_HUT_MIN_BODIES {
+        return barnes_hut::accelerations(bodies, config);
+    }
+    let mut forces = vec![Vec2::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let force = bodies[i].force_from(&bodies[j], config);
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+    forces
+        .iter()
+        .zip(bodies)
+        .map(|(force, body)| {
+            if body.fixed {
+                Vec2::ZERO
+            } else {
+                *force * config.g / body.mass
+            }
+        })
+        .collect()
+}
+
+/// Returns the states a copy of the bodies goes through over some steps,
+/// leaving the bodies themselves untouched.
+///
+/// The prediction ends early if a collision would stop the simulation.
+pub fn predict(bodies: &[Body], config: &SimConfig, steps: usize) -> Vec<Vec<Body>> {
+    let mut bodies = bodies.to_vec();
+    let mut states = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        if !step(&mut bodies, config, config.dt) {
+            break;
+        }
+        states.push(bodies.clone());
+    }
+    states
+}
+
+/// Returns in how many steps a copy of the bodies first collides, or nothing
+/// if it does not within some steps, leaving the bodies themselves
+/// untouched.
+///
+/// Like predictions, this is only meaningful for a short while, as the
+/// dynamics are chaotic.
+pub fn steps_until_collision(bodies: &[Body], config: &SimConfig, steps: usize) -> Option<usize> {
+    let mut bodies = bodies.to_vec();
+    let count = bodies.len();
+    (1..=steps).find(|_| {
+        !step(&mut bodies, config, config.dt)
+            || bodies.len() < count
+            || has_collision(&bodies, config)
+    })
+}
+
+/// Merges any colliding bodies into one.
+pub fn merge_collisions(bodies: &mut Vec<Body>, config: &SimConfig) -> f32 {
+    let kinetic = kinetic_energy(bodies);
+    let mut i = 0;
+    while i < bodies.len() {
+        let mut j = i + 1;
+        while j < bodies.len() {
+            if bodies[i].collides_with(&bodies[j], config) {
+                let other = bodies.remove(j);
+                bodies[i].merge(&other, config);
+                // The merged body is larger now, so it might reach bodies
+                // already checked.
+                j = i + 1;
+            } else {
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+    kinetic - kinetic_energy(bodies)
+}
+
+/// Returns flashes where bodies started overlapping or merged during a step.
+///
+/// Overlapping bodies flash between their centres, weighted by mass, and
+/// merged ones where the merged body is, which is the same point.
+fn collision_points(previous: &[Body], bodies: &[Body], config: &SimConfig) -> Vec<Effect> {
+    let overlapping = |bodies: &[Body], a: usize, b: usize| {
+        let find = |id| bodies.iter().find(|body| body.id == id);
+        matches!((find(a), find(b)), (Some(a), Some(b)) if a.collides_with(b, config))
+    };
+    let mut effects = Vec::new();
+    for (i, a) in bodies.iter().enumerate() {
+        for b in &bodies[i + 1..] {
+            if a.collides_with(b, config) && !overlapping(previous, a.id, b.id) {
+                let weight = b.mass / (a.mass + b.mass);
+                effects.push(Effect::new(
+                    a.position + a.delta_to(b, config) * weight,
+                    a.radius.min(b.radius),
+                    (a.velocity - b.velocity).length(),
+                ));
+            }
+        }
+    }
+    for body in bodies {
+        let Some(before) = previous
+            .iter()
+            .find(|before| before.id == body.id && before.mass < body.mass)
+        else {
+            continue;
+        };
+        // The bodies merged into this one are among those which are gone.
+        let impact = previous
+            .iter()
+            .filter(|other| bodies.iter().all(|body| body.id != other.id))
+            .map(|other| (other.velocity - before.velocity).length())
+            .fold(0.0, f32::max);
+        effects.push(Effect::new(body.position, body.radius, impact));
+    }
+    effects
+}
+
+/// Returns the centre of mass of all bodies.
+pub fn centre_of_mass(bodies: &[Body]) -> Option<Vec2> {
+    let mass: f32 = bodies.iter().map(|body| body.mass).sum();
+    if mass <= 0.0 {
+        return None;
+    }
+    Some(
+        bodies
+            .iter()
+            .map(|body| body.mass * body.position)
+            .fold(Vec2::ZERO, |acc, moment| acc + moment)
+            / mass,
+    )
+}
+
+/// Returns the total kinetic, potential, and mechanical energy of all bodies.
+///
+/// The potential energy of each pair is `-G * m1 * m2 / sqrt(r^2 + e^2)` for
+/// a softening length `e`, which stays finite for overlapping bodies.
+pub fn total_energy(bodies: &[Body], config: &SimConfig) -> (f32, f32, f32) {
+    let kinetic = kinetic_energy(bodies);
+    let potential: f32 = pairs(bodies)
+        .map(|(a, b)| {
+            let distance = a.delta_to(b, config).length();
+            potential(distance, a.mass * b.mass, a.radius + b.radius, config)
+        })
+        .sum();
+    (kinetic, potential, kinetic + potential)
+}
+
+/// Returns the gravitational potential at a point, the potential energy a
+/// unit mass there would have, taking the wrap into account like the forces
+/// do.
+pub fn potential_at(point: Vec2, bodies: &[Body], config: &SimConfig) -> f32 {
+    bodies
+        .iter()
+        .map(|body| {
+            let distance = wrap_delta(point - body.position, config).length();
+            potential(distance, body.mass, body.radius, config)
+        })
+        .sum()
+}
+
+/// Returns the potential energy of two masses some distance apart, with the
+/// product of their masses and the distance at which they touch.
+fn potential(distance: f32, masses: f32, contact: f32, config: &SimConfig) -> f32 {
+    -config.g
+        * masses
+        * match config.force_law {
+            ForceLaw::PointMass => {
+                1.0 / (distance * distance + config.softening * config.softening).sqrt()
+            }
+            // The potential within the overlap matches the linear force, and
+            // meets the outside one at contact.
+            ForceLaw::UniformDisk if distance < contact => {
+                (3.0 * contact * contact - distance * distance) / (2.0 * contact.powi(3))
+            }
+            ForceLaw::UniformDisk => 1.0 / distance,
+        }
+}
+
+/// Returns the total kinetic energy of all bodies.
+pub fn kinetic_energy(bodies: &[Body]) -> f32 {
+    bodies.iter().map(Body::kinetic_energy).sum()
+}
+
+/// Returns the total linear momentum of all bodies.
+pub fn total_momentum(bodies: &[Body]) -> Vec2 {
+    bodies
+        .iter()
+        .map(|body| body.mass * body.velocity)
+        .fold(Vec2::ZERO, |acc, momentum| acc + momentum)
+}
+
+/// Returns the total angular momentum of all bodies about their centre of
+/// mass, `sum(m * (r - c) x v)`, or nothing if they have no mass.
+///
+/// Positions are taken as they are, so this is only meaningful if the world
+/// does not wrap around.
+pub fn angular_momentum(bodies: &[Body]) -> Option<f32> {
+    let centre = centre_of_mass(bodies)?;
+    Some(
+        bodies
+            .iter()
+            .map(|body| body.mass * (body.position - centre).perp_dot(body.velocity))
+            .sum(),
+    )
+}
+
+/// Returns the index of the body at a position, if any.
+pub fn body_at(position: Vec2, bodies: &[Body]) -> Option<usize> {
+    // Bodies drawn later are on top.
+    bodies
+        .iter()
+        .rposition(|body| body.position.distance(position) <= body.radius)
+}
+
+/// Returns the smallest distance between any two bodies, or infinity if there
+/// are fewer than two.
+pub fn min_pairwise_distance(bodies: &[Body], config: &SimConfig) -> f32 {
+    pairs(bodies)
+        .map(|(a, b)| a.delta_to(b, config).length())
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Returns how far apart two states of the same bodies are, or nothing if
+/// they do not have the same bodies.
+///
+/// Positions are measured relative to the size of the world, and velocities
+/// relative to the root mean square speed of the first state, so both
+/// contribute equally to the root mean square distance over all bodies.
+fn state_distance(a: &[Body], b: &[Body], config: &SimConfig) -> Option<f32> {
+    if a.is_empty() || a.len() != b.len() || a.iter().zip(b).any(|(a, b)| a.id != b.id) {
+        return None;
+    }
+    let count = a.len() as f32;
+    let scale = config.world.min_element();
+    let speed = (a
+        .iter()
+        .map(|body| body.velocity.length_squared())
+        .sum::<f32>()
+        / count)
+        .sqrt();
+    let speed = if speed > f32::EPSILON { speed } else { 1.0 };
+    let sum: f32 = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            (a.delta_to(b, config).length() / scale).powi(2)
+                + ((b.velocity - a.velocity).length() / speed).powi(2)
+        })
+        .sum();
+    Some((sum / count).sqrt())
+}
+
+/// Returns the index of a body which is escaping the others, if any.
+///
+/// A body is escaping if it is far away from the centre of mass of all
+/// bodies, and unbound from the others.
+///
+/// Nothing gets far away if the world wraps, so there distances are measured
+/// to the nearest image of each other body, and a quarter of the shorter side
+/// of the world counts as far. An [`Ejection`] only counts a body escaping
+/// there as escaped once it stays unbound for a while.
+pub fn ejected_body(bodies: &[Body], config: &SimConfig) -> Option<usize> {
+    let far = if config.boundary == Boundary::Wrap {
+        config
+            .ejection_distance
+            .min(config.world.min_element() / 4.0)
+    } else {
+        config.ejection_distance
+    };
+    bodies.iter().position(|body| {
+        let others: Vec<Body> = bodies
+            .iter()
+            .filter(|other| other.id != body.id)
+            .copied()
+            .collect();
+        let mass: f32 = others.iter().map(|other| other.mass).sum();
+        if mass <= 0.0 {
+            return false;
+        }
+        // The centre of mass of the others, relative to the body.
+        let offset = others
+            .iter()
+            .map(|other| other.mass * ejection_delta(body, other, config))
+            .fold(Vec2::ZERO, |acc, moment| acc + moment)
+            / mass;
+        // The centre of mass of all bodies lies between the body and the
+        // others, closer to the heavier side.
+        offset.length() * mass / (mass + body.mass) >= far && unbound(body, bodies, config)
+    })
+}
+
+/// Returns true if a body moves faster than the escape velocity of the
+/// others, relative to their centre of mass, ignoring softening.
+///
+/// The potential is summed over each of the others rather than taken from
+/// their centre of mass, so a body orbiting one of a wide pair is still
+/// bound to it.
+fn unbound(body: &Body, bodies: &[Body], config: &SimConfig) -> bool {
+    let others: Vec<Body> = bodies
+        .iter()
+        .filter(|other| other.id != body.id)
+        .copied()
+        .collect();
+    let mass: f32 = others.iter().map(|other| other.mass).sum();
+    if mass <= 0.0 {
+        return false;
+    }
+    let potential: f32 = others
+        .iter()
+        .map(|other| config.g * other.mass / ejection_delta(body, other, config).length())
+        .sum();
+    let velocity = body.velocity - total_momentum(&others) / mass;
+    0.5 * velocity.length_squared() > potential
+}
+
+/// Returns the vector from one body to another for detecting escapes, to the
+/// nearest image of the other if the world wraps.
+fn ejection_delta(body: &Body, other: &Body, config: &SimConfig) -> Vec2 {
+    let delta = other.position - body.position;
+    if config.boundary == Boundary::Wrap {
+        delta - config.world * (delta / config.world).round()
+    } else {
+        delta
+    }
+}
+
+/// Watches for a body escaping the others over the steps of a scenario.
+#[derive(Clone, Copy, Default)]
+pub struct Ejection {
+    /// The id of the body which is escaping, if any.
+    id: Option<usize>,
+    /// How many steps it has stayed unbound since it started escaping.
+    steps: u32,
+}
+
+impl Ejection {
+    /// Checks the bodies after a step, and returns the index of a body which
+    /// escaped the others, if any.
+    ///
+    /// Where the world does not wrap, escaping bodies count as escaped right
+    /// away. Where it does, they keep coming back around to the others, and
+    /// a slingshot can leave a body unbound for a moment, so it only counts
+    /// once it stays unbound for [`WRAP_EJECTION_STEPS`] steps.
+    pub fn update(&mut self, bodies: &[Body], config: &SimConfig) -> Option<usize> {
+        if config.boundary != Boundary::Wrap {
+            return ejected_body(bodies, config);
+        }
+        let escaping = self
+            .id
+            .and_then(|id| bodies.iter().position(|body| body.id == id))
+            .filter(|&idx| unbound(&bodies[idx], bodies, config));
+        if escaping.is_some() {
+            self.steps += 1;
+        } else {
+            self.id = ejected_body(bodies, config).map(|idx| bodies[idx].id);
+            self.steps = 0;
+        }
+        escaping.filter(|_| self.steps >= WRAP_EJECTION_STEPS)
+    }
+}
+
+/// Returns the gravitational force between two masses some vector apart,
+/// along that vector, without the gravitational constant.
+///
+/// The contact distance is how far apart the masses start overlapping, which
+/// only matters for the uniform disk force law.
+fn gravity(delta: Vec2, mass: f32, other_mass: f32, contact: f32, config: &SimConfig) -> Vec2 {
+    let distance = delta.length();
+    if distance <= f32::EPSILON {
+        // Bodies on top of each other don't pull in any direction.
+        return Vec2::ZERO;
+    }
+    let direction = delta / distance;
+    let force = match config.force_law {
+        ForceLaw::PointMass => {
+            (mass * other_mass) / (distance * distance + config.softening * config.softening)
+        }
+        ForceLaw::UniformDisk if distance < contact => {
+            (mass * other_mass) * distance / contact.powi(3)
+        }
+        ForceLaw::UniformDisk => (mass * other_mass) / (distance * distance),
+    };
+    direction * force
+}
+
+/// Reduces a vector between two positions to the shortest one around the
+/// edges of the world, if gravity wraps.
+fn wrap_delta(delta: Vec2, config: &SimConfig) -> Vec2 {
+    if !config.wrap_gravity {
+        return delta;
+    }
+    delta - config.world * (delta / config.world).round()
+}
+
+/// Returns every pair of bodies once.
+pub fn pairs(bodies: &[Body]) -> impl Iterator<Item = (&Body, &Body)> {
+    bodies
+        .iter()
+        .enumerate()
+        .flat_map(move |(i, a)| bodies[i + 1..].iter().map(move |b| (a, b)))
+}
+
+/// Returns true if any two bodies are colliding.
+pub fn has_collision(bodies: &[Body], config: &SimConfig) -> bool {
+    pairs(bodies).any(|(a, b)| a.collides_with(b, config))
+}
+
+/// Advances all bodies by one step using velocity Verlet integration.
+///
+/// Positions are advanced using the acceleration at the current positions,
+/// then velocities are updated using the average of the old and new
+/// accelerations. Bodies which bounced off another this step just coast.
+///
+/// Returns the kinetic energy lost in collisions.
+fn verlet_step(bodies: &mut [Body], config: &SimConfig, dt: f32) -> f32 {
+    let old_accelerations = accelerations(bodies, config);
+    let kinetic = kinetic_energy(bodies);
+    let collided = bounce_collisions(bodies, config);
+    let dissipated = kinetic - kinetic_energy(bodies);
+
+    for ((body, acceleration), collided) in bodies.iter_mut().zip(&old_accelerations).zip(&collided)
+    {
+        if *collided {
+            body.update_position(dt, config);
+        } else {
+            body.position += body.velocity * dt + 0.5 * *acceleration * dt * dt;
+            body.apply_boundary(config);
+        }
+    }
+
+    let new_accelerations = accelerations(bodies, config);
+    for (((body, acceleration), new_acceleration), collided) in bodies
+        .iter_mut()
+        .zip(&old_accelerations)
+        .zip(&new_accelerations)
+        .zip(&collided)
+    {
+        if !collided {
+            body.velocity += 0.5 * (*acceleration + *new_acceleration) * dt;
+        }
+    }
+    dissipated
+}
+
+/// Advances all bodies by one step using classic fourth-order Runge-Kutta
+/// integration.
+///
+/// The acceleration field of the whole system is evaluated at four trial
+/// states, which are then combined into a weighted average. Bodies which
+/// bounced off another this step just coast.
+///
+/// Returns the kinetic energy lost in collisions.
+fn rk4_step(bodies: &mut [Body], config: &SimConfig, dt: f32) -> f32 {
+    let kinetic = kinetic_energy(bodies);
+    let collided = bounce_collisions(bodies, config);
+    let dissipated = kinetic - kinetic_energy(bodies);
+
+    let k1 = derivatives(bodies, config);
+    let k2 = derivatives(&advance(bodies, &k1, 0.5 * dt), config);
+    let k3 = derivatives(&advance(bodies, &k2, 0.5 * dt), config);
+    let k4 = derivatives(&advance(bodies, &k3, dt), config);
+
+    for (idx, body) in bodies.iter_mut().enumerate() {
+        if collided[idx] {
+            body.update_position(dt, config);
+        } else {
+            body.position += (k1[idx].0 + 2.0 * k2[idx].0 + 2.0 * k3[idx].0 + k4[idx].0) * dt / 6.0;
+            body.velocity += (k1[idx].1 + 2.0 * k2[idx].1 + 2.0 * k3[idx].1 + k4[idx].1) * dt / 6.0;
+            body.apply_boundary(config);
+        }
+    }
+    dissipated
+}
+
+/// Bounces colliding bodies off each other if collisions are elastic, and
+/// returns which bodies collided.
+fn bounce_collisions(bodies: &mut [Body], config: &SimConfig) -> Vec<bool> {
+    if config.collision_mode == CollisionMode::Elastic {
+        resolve_collisions(bodies, config)
+    } else {
+        vec![false; bodies.len()]
+    }
+}
+
+/// Bounces all colliding bodies off each other, and returns which bodies
+/// collided.
+///
+/// Contacts are resolved one pair at a time, each exchanging equal and
+/// opposite impulses, so total momentum is conserved exactly, and with full
+/// restitution kinetic energy too, even where a body touches several others
+/// at once. As resolving one contact can send a body back into another, the
+/// pairs are swept again until none are approaching each other anymore, up to
+/// a limit.
+pub fn resolve_collisions(bodies: &mut [Body], config: &SimConfig) -> Vec<bool> {
+    let mut collided = vec![false; bodies.len()];
+    for _ in 0..MAX_COLLISION_PASSES {
+        let mut bounced = false;
+        for j in 1..bodies.len() {
+            let (head, tail) = bodies.split_at_mut(j);
+            for (i, body) in head.iter_mut().enumerate() {
+                if let Some(approaching) = body.bounce(&mut tail[0], config) {
+                    collided[i] = true;
+                    collided[j] = true;
+                    bounced |= approaching;
+                }
+            }
+        }
+        if !bounced {
+            break;
+        }
+    }
+    collided
+}
+
+/// Returns the rate of change of position and velocity of each body.
+fn derivatives(bodies: &[Body], config: &SimConfig) -> Vec<(Vec2, Vec2)> {
+    bodies
+        .iter()
+        .map(|body| body.velocity)
+        .zip(accelerations(bodies, config))
+        .collect()
+}
+
+/// Returns a hypothetical state with all bodies advanced along the given
+/// derivatives by some time.
+fn advance(bodies: &[Body], derivatives: &[(Vec2, Vec2)], dt: f32) -> Vec<Body> {
+    let mut bodies = bodies.to_vec();
+    for (body, (velocity, acceleration)) in bodies.iter_mut().zip(derivatives) {
+        body.position += dt * *velocity;
+        body.velocity += dt * *acceleration;
+    }
+    bodies
+}
+
+/// Tunable parameters of the simulation.
+#[derive(Clone, Copy)]
+pub struct SimConfig {
+    /// What happens when two bodies collide.
+    pub collision_mode: CollisionMode,
+    /// The integrator used to advance the simulation.
+    pub integrator: Integrator,
+    /// The gravitational constant, which makes bodies repel each other if
+    /// negative.
+    pub g: f32,
+    /// The time step multiplier, where 1 is one step per frame at the target
+    /// frame rate.
+    pub dt: f32,
+    /// How the force between bodies depends on their distance.
+    pub force_law: ForceLaw,
+    /// The Plummer softening length, which limits the force between point
+    /// masses passing very close to each other.
+    pub softening: f32,
+    /// How strongly bodies are slowed down in proportion to their speed, as
+    /// if moving through a thin medium, per step. This is not physical, but
+    /// lets systems bleed energy and settle down.
+    pub damping: f32,
+    /// The coefficient of restitution of elastic collisions, between 0 for
+    /// perfectly inelastic and 1 for perfectly elastic.
+    pub restitution: f32,
+    /// What happens to bodies at the edges of the world.
+    pub boundary: Boundary,
+    /// Whether gravity and collisions act across the edges of the world,
+    /// using the shortest distance between bodies around them.
+    ///
+    /// This follows whether the boundary wraps when the boundary is set, but
+    /// can be changed independently. Wrapping positions without wrapping
+    /// gravity, or the other way around, is physically unusual, as bodies
+    /// suddenly feel completely different forces when crossing an edge, but
+    /// can be interesting to experiment with.
+    pub wrap_gravity: bool,
+    /// The size of the world, which follows the window unless running
+    /// headless.
+    pub world: Vec2,
+    /// How the radius of bodies follows from their mass.
+    pub radius_mode: RadiusMode,
+    /// The density of bodies, if their radius follows from it.
+    pub density: f32,
+    /// How much trails fade each step, between 0 for instantly and 1 for
+    /// never.
+    pub trail_fade: f32,
+    /// How far a body needs to be from the centre of mass to count as having
+    /// escaped, if it is fast enough.
+    pub ejection_distance: f32,
+    /// Whether the simulation stops when a body escapes.
+    pub stop_on_ejection: bool,
+    /// Whether gravity is approximated using Barnes-Hut, if there are enough
+    /// bodies for it to be worth it.
+    pub barnes_hut: bool,
+    /// The opening angle of the Barnes-Hut approximation, below which a group
+    /// of bodies acts as one. Lower is more accurate, but slower.
+    pub theta: f32,
+    /// Whether steps are split into sub-steps during close encounters.
+    pub adaptive: bool,
+    /// The distance between the closest two bodies below which steps are
+    /// split into sub-steps with adaptive time steps, halving the sub-step
+    /// with the distance.
+    pub adaptive_distance: f32,
+    /// The most trail points kept at once, regardless of how slowly they
+    /// fade.
+    pub max_trails: usize,
+    /// Whether trails stay as they are, neither fading nor growing, while
+    /// the bodies keep moving.
+    pub freeze_trails: bool,
+    /// How close the bodies need to get to their initial state to count as
+    /// having returned to it, as a fraction of the world size and the initial
+    /// speeds.
+    pub period_tolerance: f32,
+    /// The smallest mass of random bodies.
+    pub min_mass: f32,
+    /// The largest mass of random bodies.
+    pub max_mass: f32,
+    /// The fraction of the world left free along each edge when placing
+    /// random bodies, from 0 for the whole world to 0.5 for its centre.
+    pub spawn_margin: f32,
+    /// The largest initial velocity of random bodies along either axis, in
+    /// AU per year with units.
+    pub max_velocity: f32,
+    /// The colours of random bodies.
+    pub palette: Palette,
+    /// The physical units the simulation is shown in, if any, in which
+    /// masses are in solar masses.
+    pub units: Option<Units>,
+}
+
+impl SimConfig {
+    /// Returns the radius of a body of some mass.
+    pub fn radius(&self, mass: f32) -> f32 {
+        match self.radius_mode {
+            RadiusMode::Mass => mass,
+            RadiusMode::Sphere => (mass / self.density).cbrt(),
+            RadiusMode::Disk => (mass / self.density).sqrt(),
+        }
+    }
+
+    /// Returns how many steps trail points last before they are removed.
+    ///
+    /// Trails which do not fade at all last until there are too many points.
+    pub fn trail_length(&self) -> u32 {
+        if self.trail_fade >= 1.0 {
+            return self.max_trails as u32;
+        }
+        (MIN_TRAIL_ALPHA.ln() / self.trail_fade.ln()) as u32
+    }
+
+    /// Shows the simulation in physical units, setting gravity to match.
+    pub fn set_units(&mut self, units: Units) {
+        self.units = Some(units);
+        self.g = units.g();
+    }
+
+    /// Returns the largest initial velocity of random bodies along either
+    /// axis, in pixels per step.
+    pub fn max_speed(&self) -> f32 {
+        match self.units {
+            Some(units) => units.speed_to_pixels(self.max_velocity),
+            None => self.max_velocity,
+        }
+    }
+
+    /// Sets the boundary, wrapping gravity along with positions.
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.wrap_gravity = boundary == Boundary::Wrap;
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            collision_mode: CollisionMode::Stop,
+            integrator: Integrator::Euler,
+            g: 9.81,
+            dt: 1.0,
+            softening: 2.0,
+            damping: 0.0,
+            restitution: 1.0,
+            radius_mode: RadiusMode::Sphere,
+            force_law: ForceLaw::PointMass,
+            density: 1.0 / 64.0,
+            trail_fade: 0.995,
+            period_tolerance: 0.02,
+            min_mass: 1.0,
+            max_mass: 10.0,
+            spawn_margin: 0.25,
+            max_velocity: 1.0,
+            palette: Palette::Random,
+            units: None,
+            max_trails: 20_000,
+            freeze_trails: false,
+            barnes_hut: false,
+            theta: 0.5,
+            adaptive: false,
+            adaptive_distance: 50.0,
+            ejection_distance: 1000.0,
+            stop_on_ejection: false,
+            boundary: Boundary::Wrap,
+            wrap_gravity: true,
+            world: vec2(800.0, 600.0),
+        }
+    }
+}
+
+/// What happens when two bodies collide.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CollisionMode {
+    /// Stop the simulation.
+    Stop,
+    /// Bounce the bodies off each other.
+    Elastic,
+    /// Merge the bodies into one.
+    Merge,
+    /// Let the bodies pass through each other, relying on softening to keep
+    /// the forces between them finite.
+    PassThrough,
+}
+
+impl CollisionMode {
+    /// Toggles to the next collision mode.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            CollisionMode::Stop => CollisionMode::Elastic,
+            CollisionMode::Elastic => CollisionMode::Merge,
+            CollisionMode::Merge => CollisionMode::PassThrough,
+            CollisionMode::PassThrough => CollisionMode::Stop,
+        }
+    }
+}
+
+impl std::fmt::Display for CollisionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollisionMode::Stop => write!(f, "stop"),
+            CollisionMode::Elastic => write!(f, "elastic"),
+            CollisionMode::Merge => write!(f, "merge"),
+            CollisionMode::PassThrough => write!(f, "pass-through"),
+        }
+    }
+}
+
+impl std::str::FromStr for CollisionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "stop" => Ok(CollisionMode::Stop),
+            "elastic" => Ok(CollisionMode::Elastic),
+            "merge" => Ok(CollisionMode::Merge),
+            "pass-through" | "pass_through" => Ok(CollisionMode::PassThrough),
+            _ => Err(format!("unknown collision mode '{s}'")),
+        }
+    }
+}
+
+/// What happens to bodies at the screen edges.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Bodies wrap around to the opposite edge, and feel each other's
+    /// gravity across the edges.
+    Wrap,
+    /// Bodies bounce off the edges.
+    Bounce,
+    /// There are no edges, bodies can fly off forever.
+    Open,
+}
+
+impl Boundary {
+    /// Toggles to the next boundary.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            Boundary::Wrap => Boundary::Bounce,
+            Boundary::Bounce => Boundary::Open,
+            Boundary::Open => Boundary::Wrap,
+        }
+    }
+}
+
+impl std::fmt::Display for Boundary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Boundary::Wrap => write!(f, "wrap"),
+            Boundary::Bounce => write!(f, "bounce"),
+            Boundary::Open => write!(f, "open"),
+        }
+    }
+}
+
+impl std::str::FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wrap" => Ok(Boundary::Wrap),
+            "bounce" => Ok(Boundary::Bounce),
+            "open" => Ok(Boundary::Open),
+            _ => Err(format!("unknown boundary '{s}'")),
+        }
+    }
+}
+
+/// How the force between two bodies depends on their distance `d`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ForceLaw {
+    /// All mass is at the centre of a body, so the force is
+    /// `m1 m2 / (d^2 + e^2)`, where the softening length `e` keeps it finite
+    /// as bodies pass through each other.
+    PointMass,
+    /// The mass is spread evenly over a body, so the force is `m1 m2 / d^2`
+    /// while bodies are apart, but once they overlap, only the mass within
+    /// the distance pulls, so it falls off linearly to zero as
+    /// `m1 m2 d / R^3`, where `R` is the sum of their radii.
+    UniformDisk,
+}
+
+impl ForceLaw {
+    /// Toggles to the other force law.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            ForceLaw::PointMass => ForceLaw::UniformDisk,
+            ForceLaw::UniformDisk => ForceLaw::PointMass,
+        }
+    }
+}
+
+impl std::fmt::Display for ForceLaw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForceLaw::PointMass => write!(f, "point mass"),
+            ForceLaw::UniformDisk => write!(f, "uniform disk"),
+        }
+    }
+}
+
+/// The colours random bodies are drawn from.
+///
+/// Each body's colour comes from its own generator, seeded by the scenario's
+/// seed and the body's id, so the rest of a scenario is the same for a seed
+/// whichever palette it is coloured with, and a scenario can be recoloured
+/// with the colours it would have had with another palette.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    /// Any colour which is not too dark to see on black.
+    Random,
+    /// Soft, light colours.
+    Pastel,
+    /// Fully saturated, bright colours.
+    Neon,
+    /// Shades of grey.
+    Grayscale,
+    /// A fixed colour for each body by its id, red, green, blue, and so on,
+    /// so bodies look the same across runs.
+    ById,
+}
+
+impl Palette {
+    /// Toggles to the next palette.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            Palette::Random => Palette::Pastel,
+            Palette::Pastel => Palette::Neon,
+            Palette::Neon => Palette::Grayscale,
+            Palette::Grayscale => Palette::ById,
+            Palette::ById => Palette::Random,
+        }
+    }
+
+    /// Returns the colour from the palette of the body with an id in the
+    /// scenario from a seed.
+    pub fn colour(&self, seed: u64, id: usize) -> Color {
+        let mut rng = Rng::new(seed ^ (id as u64 + 1).wrapping_mul(0xd1b5_4a32_d192_ed03));
+        match self {
+            Palette::Random => Color::new(
+                rng.gen_range(0.2, 1.0),
+                rng.gen_range(0.2, 1.0),
+                rng.gen_range(0.2, 1.0),
+                1.0,
+            ),
+            Palette::Pastel => Color::new(
+                rng.gen_range(0.65, 1.0),
+                rng.gen_range(0.65, 1.0),
+                rng.gen_range(0.65, 1.0),
+                1.0,
+            ),
+            Palette::Neon => {
+                // A fully saturated hue, slightly washed out towards white.
+                let hue = rng.gen_range(0.0, 6.0);
+                let white = rng.gen_range(0.0, 0.25);
+                let x = 1.0 - (hue % 2.0 - 1.0).abs();
+                let (r, g, b) = match hue as u32 {
+                    0 => (1.0, x, 0.0),
+                    1 => (x, 1.0, 0.0),
+                    2 => (0.0, 1.0, x),
+                    3 => (0.0, x, 1.0),
+                    4 => (x, 0.0, 1.0),
+                    _ => (1.0, 0.0, x),
+                };
+                Color::new(
+                    r + (1.0 - r) * white,
+                    g + (1.0 - g) * white,
+                    b + (1.0 - b) * white,
+                    1.0,
+                )
+            }
+            Palette::Grayscale => {
+                let level = rng.gen_range(0.3, 1.0);
+                Color::new(level, level, level, 1.0)
+            }
+            Palette::ById => ID_COLOURS[id % ID_COLOURS.len()],
+        }
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Palette::Random => write!(f, "random"),
+            Palette::Pastel => write!(f, "pastel"),
+            Palette::Neon => write!(f, "neon"),
+            Palette::Grayscale => write!(f, "grayscale"),
+            Palette::ById => write!(f, "by id"),
+        }
+    }
+}
+
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(Palette::Random),
+            "pastel" => Ok(Palette::Pastel),
+            "neon" => Ok(Palette::Neon),
+            "grayscale" | "greyscale" => Ok(Palette::Grayscale),
+            "id" | "by-id" => Ok(Palette::ById),
+            _ => Err(format!("unknown palette '{s}'")),
+        }
+    }
+}
+
+/// How the radius of a body follows from its mass.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RadiusMode {
+    /// The radius equals the mass.
+    Mass,
+    /// The body is a sphere of constant density.
+    Sphere,
+    /// The body is a disk of constant density.
+    Disk,
+}
+
+impl RadiusMode {
+    /// Toggles to the next radius mode.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            RadiusMode::Mass => RadiusMode::Sphere,
+            RadiusMode::Sphere => RadiusMode::Disk,
+            RadiusMode::Disk => RadiusMode::Mass,
+        }
+    }
+}
+
+impl std::fmt::Display for RadiusMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadiusMode::Mass => write!(f, "mass"),
+            RadiusMode::Sphere => write!(f, "sphere"),
+            RadiusMode::Disk => write!(f, "disk"),
+        }
+    }
+}
+
+/// The numerical integration scheme used to advance the simulation.
+#[derive(Clone, Copy)]
+pub enum Integrator {
+    /// Semi-implicit Euler, fast but drifts in energy over long runs.
+    Euler,
+    /// Velocity Verlet, which keeps energy and angular momentum bounded.
+    Verlet,
+    /// Classic fourth-order Runge-Kutta, the most accurate per step.
+    RK4,
+}
+
+impl Integrator {
+    /// Toggles to the next integrator.
+    pub fn toggle(&mut self) {
+        *self = match self {
+            Integrator::Euler => Integrator::Verlet,
+            Integrator::Verlet => Integrator::RK4,
+            Integrator::RK4 => Integrator::Euler,
+        }
+    }
+}
+
+impl std::fmt::Display for Integrator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Integrator::Euler => write!(f, "Euler"),
+            Integrator::Verlet => write!(f, "Verlet"),
+            Integrator::RK4 => write!(f, "RK4"),
+        }
+    }
+}
+
+impl std::str::FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "euler" => Ok(Integrator::Euler),
+            "verlet" => Ok(Integrator::Verlet),
+            "rk4" => Ok(Integrator::RK4),
+            _ => Err(format!("unknown integrator '{s}'")),
+        }
+    }
+}
+
+/// A body in the simulation.
+#[derive(Clone, Copy)]
+pub struct Body {
+    pub id: usize,
+    pub colour: Color,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+    /// The radius used for drawing and collisions, independent of mass.
+    pub radius: f32,
+    /// Whether the body is held in place, still attracting other bodies but
+    /// never moving itself.
+    pub fixed: bool,
+}
+
+impl Body {
+    /// Creates a new body with random properties.
+    pub fn new_random(id: usize, config: &SimConfig, rng: &mut Rng) -> Self {
+        let colour = config.palette.colour(rng.seed(), id);
+        let margin = config.spawn_margin.clamp(0.0, 0.5);
+        let position = vec2(
+            rng.gen_range(config.world.x * margin, config.world.x * (1.0 - margin)),
+            rng.gen_range(config.world.y * margin, config.world.y * (1.0 - margin)),
+        );
+        let max_speed = config.max_speed();
+        let velocity = vec2(
+            rng.gen_range(-max_speed, max_speed),
+            rng.gen_range(-max_speed, max_speed),
+        );
+        let mass = rng.gen_range(config.min_mass, config.max_mass);
+        Self {
+            id,
+            colour,
+            position,
+            velocity,
+            mass,
+            radius: config.radius(mass),
+            fixed: false,
+        }
+    }
+
+    /// Returns a copy of the body with a new random position and velocity,
+    /// but the same mass and colour.
+    pub fn new_random_orbit(&self, config: &SimConfig, rng: &mut Rng) -> Self {
+        Self {
+            id: self.id,
+            colour: self.colour,
+            mass: self.mass,
+            radius: self.radius,
+            fixed: self.fixed,
+            ..Self::new_random(self.id, config, rng)
+        }
+    }
+
+    /// Sets the mass of the body, resizing it to match.
+    pub fn set_mass(&mut self, mass: f32, config: &SimConfig) {
+        self.mass = mass;
+        self.radius = config.radius(mass);
+    }
+
+    /// Creates a number of new bodies with random properties.
+    pub fn new_random_bodies(count: usize, config: &SimConfig, rng: &mut Rng) -> Vec<Self> {
+        (0..count)
+            .map(|id| Self::new_random(id, config, rng))
+            .collect()
+    }
+
+    /// Holds the body in place, or releases it again.
+    ///
+    /// Fixed bodies are stopped, so they stay put no matter the integrator.
+    pub fn toggle_fixed(&mut self) {
+        self.fixed = !self.fixed;
+        self.velocity = Vec2::ZERO;
+    }
+
+    /// Updates the velocity of the body based on its acceleration, unless it
+    /// is fixed.
+    pub fn update_velocity(&mut self, acceleration: Vec2, dt: f32) {
+        if !self.fixed {
+            self.velocity += acceleration * dt;
+        }
+    }
+
+    /// Returns the kinetic energy of the body.
+    pub fn kinetic_energy(&self) -> f32 {
+        0.5 * self.mass * self.velocity.length_squared()
+    }
+
+    /// Bounces this body and another off each other if they collide.
+    ///
+    /// Only the velocity components along the collision normal, the line
+    /// connecting the centres, change, the tangential components are left
+    /// untouched. With a coefficient of restitution `e`, the bodies exchange
+    /// an impulse of `(1 + e) * (u1 - u2) * m1 * m2 / (m1 + m2)` along the
+    /// normal, so at `e = 1` the collision is perfectly elastic, and at
+    /// `e = 0` both bodies move on together.
+    ///
+    /// Overlapping bodies are also pushed apart along the normal until they
+    /// are just touching, with the lighter body moving further, so they do
+    /// not collide again on the next step.
+    ///
+    /// Fixed bodies act as if infinitely heavy, so they never move, and
+    /// bodies bounce off them as off a wall.
+    ///
+    /// Returns nothing if the bodies do not collide, or whether they were
+    /// still approaching each other and bounced.
+    fn bounce(&mut self, other: &mut Self, config: &SimConfig) -> Option<bool> {
+        if !self.collides_with(other, config) {
+            return None;
+        }
+        // Inverse masses, which are zero for fixed bodies.
+        let w1 = if self.fixed { 0.0 } else { 1.0 / self.mass };
+        let w2 = if other.fixed { 0.0 } else { 1.0 / other.mass };
+        if w1 + w2 <= 0.0 {
+            return Some(false);
+        }
+        let delta = self.delta_to(other, config);
+        let normal = delta.normalize_or_zero();
+
+        let overlap = self.radius + other.radius - delta.length();
+        if overlap > 0.0 {
+            let correction = normal * overlap / (w1 + w2);
+            self.position -= correction * w1;
+            other.position += correction * w2;
+            self.apply_boundary(config);
+            other.apply_boundary(config);
+        }
+
+        // Only bounce if the bodies are still approaching each other.
+        let approach = (self.velocity - other.velocity).dot(normal);
+        if approach <= 0.0 {
+            return Some(false);
+        }
+        let impulse = normal * (1.0 + config.restitution) * approach / (w1 + w2);
+        self.velocity -= impulse * w1;
+        other.velocity += impulse * w2;
+        Some(true)
+    }
+
+    /// Returns the acceleration applied to this body by other bodies, and by
+    /// damping if any.
+    ///
+    /// The bodies do not have to be the current state of the simulation, which
+    /// allows evaluating the acceleration field for hypothetical states.
+    ///
+    /// Fixed bodies do not accelerate, as in [`accelerations`].
+    pub fn acceleration(&self, bodies: &[Self], config: &SimConfig) -> Vec2 {
+        if self.fixed {
+            return Vec2::ZERO;
+        }
+        bodies
+            .iter()
+            .filter(|&body| body.id != self.id)
+            .map(|other| self.force_from(other, config))
+            .fold(Vec2::ZERO, |acc, force| acc + force)
+            * config.g
+            / self.mass
+            - config.damping * self.velocity
+    }
+
+    /// Returns the gravitational force another body applies to this one,
+    /// without the gravitational constant.
+    pub fn force_from(&self, other: &Self, config: &SimConfig) -> Vec2 {
+        gravity(
+            self.delta_to(other, config),
+            self.mass,
+            other.mass,
+            self.radius + other.radius,
+            config,
+        )
+    }
+
+    /// Returns the shortest vector from this body to another, taking the
+    /// screen wrap into account if gravity wraps.
+    ///
+    /// With wrapping, each axis is reduced to the nearest image of the other
+    /// body within half a screen, no matter how many screens apart the
+    /// positions are, such as for hypothetical states which have not been
+    /// wrapped yet.
+    pub fn delta_to(&self, other: &Self, config: &SimConfig) -> Vec2 {
+        wrap_delta(other.position - self.position, config)
+    }
+
+    /// Merges another body into this one, conserving mass and momentum.
+    ///
+    /// The merged body sits at the centre of mass of the two, and its colour
+    /// is blended in proportion to their masses. It keeps the id of the
+    /// heavier of the two, whose trail it continues.
+    ///
+    /// If only one of them is fixed, the merged body stays fixed where that
+    /// one is.
+    pub fn merge(&mut self, other: &Self, config: &SimConfig) {
+        if other.mass > self.mass {
+            self.id = other.id;
+        }
+        let mass = self.mass + other.mass;
+        let weight = other.mass / mass;
+        let position_weight = match (self.fixed, other.fixed) {
+            (false, true) => 1.0,
+            (true, false) => 0.0,
+            _ => weight,
+        };
+        self.position += self.delta_to(other, config) * position_weight;
+        self.apply_boundary(config);
+        self.fixed |= other.fixed;
+        self.velocity = if self.fixed {
+            Vec2::ZERO
+        } else {
+            (self.mass * self.velocity + other.mass * other.velocity) / mass
+        };
+        self.colour = Color::new(
+            self.colour.r + (other.colour.r - self.colour.r) * weight,
+            self.colour.g + (other.colour.g - self.colour.g) * weight,
+            self.colour.b + (other.colour.b - self.colour.b) * weight,
+            self.colour.a,
+        );
+        self.set_mass(mass, config);
+    }
+
+    /// Updates the position of the body based on its velocity, unless it is
+    /// fixed.
+    pub fn update_position(&mut self, dt: f32, config: &SimConfig) {
+        if self.fixed {
+            return;
+        }
+        self.position += self.velocity * dt;
+        self.apply_boundary(config);
+    }
+
+    /// Keeps the body within the edges of the world, depending on the boundary.
+    pub fn apply_boundary(&mut self, config: &SimConfig) {
+        match config.boundary {
+            Boundary::Wrap => {
+                if self.position.x > config.world.x {
+                    self.position.x -= config.world.x;
+                } else if self.position.x < 0. {
+                    self.position.x += config.world.x;
+                }
+                if self.position.y > config.world.y {
+                    self.position.y -= config.world.y;
+                } else if self.position.y < 0. {
+                    self.position.y += config.world.y;
+                }
+            }
+            Boundary::Bounce => {
+                let min = Vec2::splat(self.radius);
+                let max = config.world - self.radius;
+                if self.position.x < min.x {
+                    self.velocity.x = self.velocity.x.abs();
+                } else if self.position.x > max.x {
+                    self.velocity.x = -self.velocity.x.abs();
+                }
+                if self.position.y < min.y {
+                    self.velocity.y = self.velocity.y.abs();
+                } else if self.position.y > max.y {
+                    self.velocity.y = -self.velocity.y.abs();
+                }
+                self.position = self.position.clamp(min, max.max(min));
+            }
+            Boundary::Open => {}
+        }
+    }
+
+    /// Returns true if this body collides with another.
+    pub fn collides_with(&self, other: &Self, config: &SimConfig) -> bool {
+        self.delta_to(other, config).length() <= self.radius + other.radius
+    }
+}
+
+/// A trail left behind by a body.
+#[derive(Clone, Copy)]
+pub struct Trail {
+    pub body_id: usize,
+    pub position: Vec2,
+    pub colour: Color,
+    /// The speed of the body when it left the trail.
+    pub speed: f32,
+    /// How far the nearest other body was when the trail was left.
+    pub nearest: f32,
+}
+
+impl Trail {
+    /// Creates a trail at the current position of a body.
+    pub fn new(body: &Body, bodies: &[Body], config: &SimConfig) -> Self {
+        let nearest = bodies
+            .iter()
+            .filter(|other| other.id != body.id)
+            .map(|other| body.delta_to(other, config).length())
+            .fold(f32::INFINITY, f32::min);
+        Self {
+            body_id: body.id,
+            position: body.position,
+            colour: body.colour,
+            speed: body.velocity.length(),
+            nearest,
+        }
+    }
+}
+
+/// A flash where bodies collided, which expands and fades out.
+#[derive(Clone, Copy)]
+pub struct Effect {
+    pub position: Vec2,
+    /// The radius the flash starts at.
+    pub radius: f32,
+    /// How fast the bodies which collided were moving relative to each
+    /// other.
+    pub impact: f32,
+    /// How many frames ago the collision happened.
+    pub age: u32,
+}
+
+impl Effect {
+    /// Creates a new flash.
+    pub fn new(position: Vec2, radius: f32, impact: f32) -> Self {
+        Self {
+            position,
+            radius,
+            impact,
+            age: 0,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Compressing images with GIF's variant of LZW.
+//!
+//! Codes start out 9 bits wide and grow up to 12 bits as the table of codes
+//! fills up, after which the table is cleared and starts over. Codes are
+//! packed into bytes least significant bit first.
+
+use std::collections::HashMap;
+
+/// Compresses palette indices with GIF's variant of LZW, with a minimum code
+/// size of 8 bits.
+pub fn encode(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODES: u16 = 4096;
+
+    let mut output = BitWriter::default();
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = END + 1;
+    let mut code_size = 9;
+
+    output.write(CLEAR, code_size);
+    let Some((&first, rest)) = indices.split_first() else {
+        output.write(END, code_size);
+        return output.finish();
+    };
+    let mut prefix = u16::from(first);
+    for &idx in rest {
+        if let Some(&code) = codes.get(&(prefix, idx)) {
+            prefix = code;
+            continue;
+        }
+        output.write(prefix, code_size);
+        if next_code < MAX_CODES {
+            codes.insert((prefix, idx), next_code);
+            next_code += 1;
+            // The decoder adds codes one step behind, so the code size only
+            // grows once it has seen the code that fills up the current one.
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            output.write(CLEAR, code_size);
+            codes.clear();
+            next_code = END + 1;
+            code_size = 9;
+        }
+        prefix = u16::from(idx);
+    }
+    output.write(prefix, code_size);
+    // The decoder adds a code for the last one too, so it reads the end code
+    // one bit wider if that code fills up the current size.
+    if next_code == 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    output.write(END, code_size);
+    output.finish()
+}
+
+/// Packs codes of varying sizes into bytes, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
@@ -1,137 +1,1865 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use macroquad::prelude::*;
+use three_bodies::{
+    angular_momentum, body_at, centre_of_mass, has_collision, min_pairwise_distance, pairs,
+    potential_at, predict, presets, rng::Rng, scenario, search, settings, step,
+    steps_until_collision, total_energy, total_momentum, trajectory, units::Units, Body, Boundary,
+    CollisionMode, Effect, SimConfig, Trail, World, EFFECT_FRAMES, IS_WASM,
+};
 
-#[cfg(target_arch = "wasm32")]
-const IS_WASM: bool = true;
-#[cfg(not(target_arch = "wasm32"))]
-const IS_WASM: bool = false;
-
-#[macroquad::main("Three Bodies")]
-async fn main() {
-    rand::srand(42);
-    let mut bodies = [
-        Body::new_random(0),
-        Body::new_random(1),
-        Body::new_random(2),
-    ];
-    let mut trails: VecDeque<Trail> = VecDeque::new();
-    let mut running = true;
-    let mut show_ui = Ui::Full;
-    let mut auto_restart = IS_WASM;
-    let mut elastic_collisions = false;
+mod accumulation;
+mod query;
+mod recording;
+mod replay;
+mod sound;
+
+/// The number keys from 1 to 9.
+const DIGIT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// The frame rate at which one step of the simulation takes one unit of time.
+const TARGET_FPS: f32 = 60.0;
+
+/// The longest frame time used to advance the simulation, to avoid huge
+/// jumps after a stall.
+const MAX_FRAME_TIME: f32 = 0.1;
+
+/// The mass of bodies spawned by clicking.
+const SPAWN_MASS: f32 = 5.0;
+
+/// The lightest a body can be made with the scroll wheel.
+const MIN_MASS: f32 = 0.5;
+
+/// The heaviest a body can be made with the scroll wheel.
+const MAX_MASS: f32 = 100.0;
+
+/// How much the total momentum may change in a step before it is considered
+/// not conserved, to allow for rounding errors.
+const MOMENTUM_TOLERANCE: f32 = 1e-3;
+
+/// How far the total angular momentum may drift from its initial value,
+/// relative to it, before it is considered not conserved.
+const ANGULAR_MOMENTUM_TOLERANCE: f32 = 1e-2;
+
+/// How long velocity arrows are drawn, relative to the speed.
+const VELOCITY_ARROW_SCALE: f32 = 20.0;
+
+/// How long force arrows are drawn, relative to the force.
+const FORCE_ARROW_SCALE: f32 = 2000.0;
+
+/// The longest force arrow drawn, as forces get huge during close encounters.
+const MAX_FORCE_ARROW_LENGTH: f32 = 100.0;
+
+/// How much collision flashes grow over their lifetime.
+const EFFECT_GROWTH: f32 = 40.0;
+
+/// How close bodies get before the background starts turning red.
+const TENSION_DISTANCE: f32 = 100.0;
+
+/// How red the background gets when bodies touch, kept dark so trails stay
+/// visible.
+const MAX_TENSION_RED: f32 = 0.15;
+
+/// How far tidal stretching is drawn beyond the edges of a body, relative to
+/// the difference in acceleration across it.
+const TIDE_SCALE: f32 = 2000.0;
+
+/// The furthest tidal stretching is drawn beyond the edges of a body.
+const MAX_TIDE_LENGTH: f32 = 40.0;
+
+/// The most bodies distances between them are labelled for, beyond which
+/// the labels would only be clutter.
+const MAX_DISTANCE_BODIES: usize = 10;
+
+/// The slowest time can be slowed down to, where steps are only taken on
+/// some frames.
+const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+
+/// The fastest time can be sped up to, where several steps are taken each
+/// frame.
+const MAX_TIME_SCALE: f32 = 16.0;
+
+/// How much damping changes per key press.
+const DAMPING_STEP: f32 = 0.001;
+
+/// The lowest the maximum number of trail points can be set to.
+const MIN_MAX_TRAILS: usize = 1_000;
+
+/// The highest the maximum number of trail points can be set to.
+const MAX_MAX_TRAILS: usize = 1_280_000;
+
+/// The speed at which trails coloured by speed are halfway between blue and
+/// red.
+const TRAIL_SPEED_SCALE: f32 = 2.0;
+
+/// The distance to the nearest other body at which trails coloured by it are
+/// halfway between blue and yellow.
+const TRAIL_DISTANCE_SCALE: f32 = 50.0;
+
+/// How much wider than usual comet tails get at the highest speeds.
+const COMET_WIDTH: f32 = 6.0;
+
+/// How far the glow of glowing bodies reaches, relative to their radius.
+const GLOW_SCALE: f32 = 4.0;
+
+/// How many circles the glow of glowing bodies is made of.
+const GLOW_LAYERS: usize = 12;
+
+/// How far from the top of the screen the instructions may reach, to leave
+/// room for the information in the top left corner.
+const INSTRUCTIONS_TOP: f32 = 170.0;
+
+/// The distance between grid lines, in world units.
+const GRID_SPACING: f32 = 50.0;
+
+/// The size of the cells of the potential heatmap, in screen pixels.
+const HEATMAP_CELL: f32 = 16.0;
+
+/// How many steps the period is shown for after the bodies returned to their
+/// initial state.
+const PERIOD_FLASH_STEPS: u32 = 120;
+
+/// How many steps ahead predicted paths reach. The dynamics are chaotic, so
+/// predictions become meaningless quickly.
+const PREDICTION_STEPS: usize = 200;
+
+/// How many predicted steps are between the dots of a predicted path.
+const PREDICTION_DOT_INTERVAL: usize = 4;
+
+/// How many steps ahead the time until the next collision is looked for.
+const COUNTDOWN_STEPS: usize = 600;
+
+/// How many frames pass between estimates of the time until the next
+/// collision, which count down in between.
+const COUNTDOWN_FRAMES: u32 = 30;
+
+/// How many seeds are searched for a long-lived scenario, unless set on the
+/// command line.
+const SEARCH_TRIES: usize = 100;
+
+/// The most steps a scenario is run for while searching, after which it
+/// counts as stable.
+const MAX_SEARCH_STEPS: u32 = 10_000;
+
+/// How many seeds are tried each frame while searching, so the window stays
+/// responsive.
+const SEARCH_SEEDS_PER_FRAME: usize = 2;
+
+/// How far the arrow keys move the selected body each frame.
+const NUDGE_DISTANCE: f32 = 1.0;
+
+/// How much shift and the arrow keys change the selected body's velocity
+/// each frame.
+const NUDGE_SPEED: f32 = 0.01;
+
+/// How much ctrl and the arrow keys change the launch velocity of the
+/// restricted three-body preset each press.
+const LAUNCH_STEP: f32 = 0.05;
+
+fn main() {
+    let args = Args::parse();
+    if args.headless {
+        run_headless(&args);
+    } else {
+        let conf = Conf {
+            window_title: "Three Bodies".to_owned(),
+            fullscreen: args.fullscreen,
+            ..Default::default()
+        };
+        macroquad::Window::from_config(conf, run(args));
+    }
+}
+
+/// Runs the simulation in a window.
+async fn run(args: Args) {
+    let config = SimConfig {
+        world: vec2(screen_width(), screen_height()),
+        ..args.config
+    };
+    let mut found = args.search.map(|tries| {
+        let first = args.seed.unwrap_or_else(random_seed);
+        find_seed(first, tries, args.bodies, &config)
+    });
+    let mut rng = Rng::new(match found {
+        Some(found) => found.seed,
+        None => args.seed.unwrap_or_else(random_seed),
+    });
+    println!("Seed: {}", rng.seed());
+    let bodies = match &args.scenario {
+        Some(path) => scenario::load(path, &config, &mut rng).unwrap_or_else(|err| {
+            eprintln!("Failed to load scenario from {path}: {err}");
+            Body::new_random_bodies(args.bodies, &config, &mut rng)
+        }),
+        None => Body::new_random_bodies(args.bodies, &config, &mut rng),
+    };
+    let mut world = World::new(bodies, config);
+    let mut view = View::default();
+    if let Some(text) = &args.collision_text {
+        view.collision_text = text.clone();
+    }
+    let mut auto_restart = args.auto_restart;
+    let mut keep_masses = false;
+    // The launch velocity of the restricted three-body preset.
+    let mut launch = Vec2::ZERO;
+    let mut paused = false;
+    let mut time_scale: f32 = 1.0;
+    let mut step_budget: f32 = 0.0;
+    let mut momentum_drifted = false;
+    // The last estimate of the steps until the next collision, the step it
+    // was made at, and how many frames ago.
+    let mut countdown: Option<(Option<usize>, u32, u32)> = None;
+    let mut spawn_start = None;
+    let mut recording: Option<recording::Recording> = None;
+    let mut accumulation: Option<accumulation::Accumulation> = None;
+    let mut replay = replay::Replay::new();
+    let mut searching: Option<search::Search> = None;
+    let mut sounds = sound::Sounds::load().await;
+    let mut trajectory = args.record_csv.as_deref().and_then(|path| {
+        trajectory::Trajectory::create(path)
+            .map_err(|err| eprintln!("Failed to create trajectory file {path}: {err}"))
+            .ok()
+    });
 
     loop {
+        // The world follows the size of the window.
+        world.resize(vec2(screen_width(), screen_height()));
+
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+
         // Exit on escape.
         if !IS_WASM && is_key_released(KeyCode::Escape) {
+            if let Some(Err(err)) = trajectory.take().map(trajectory::Trajectory::close) {
+                eprintln!("Failed to write trajectory: {err}");
+            }
             break;
         }
 
-        // Reset on space, or if auto restart is on.
-        if is_key_released(KeyCode::Space)
-            || is_mouse_button_released(MouseButton::Left)
-            || (!running && auto_restart)
+        // Start a new scenario on space, or if auto restart is on and the
+        // current one ended.
+        if is_key_released(KeyCode::Space) || auto_restart.due(&world) {
+            rng.reseed(random_seed());
+            println!("Seed: {}", rng.seed());
+            let bodies = if keep_masses && !world.initial_bodies.is_empty() {
+                world
+                    .initial_bodies
+                    .iter()
+                    .map(|body| body.new_random_orbit(&world.config, &mut rng))
+                    .collect()
+            } else {
+                Body::new_random_bodies(args.bodies, &world.config, &mut rng)
+            };
+            world.load(bodies);
+        }
+
+        // Search for a long-lived scenario on enter, a few seeds each frame,
+        // and load it once the search is over.
+        if is_key_released(KeyCode::Enter) {
+            searching = Some(search::Search::new(
+                random_seed(),
+                args.search.unwrap_or(SEARCH_TRIES),
+                args.bodies,
+                &world.config,
+                MAX_SEARCH_STEPS,
+            ));
+        }
+        if let Some(result) = searching
+            .as_mut()
+            .and_then(|search| search.advance(SEARCH_SEEDS_PER_FRAME))
         {
-            bodies = [
-                Body::new_random(0),
-                Body::new_random(1),
-                Body::new_random(2),
-            ];
-            trails.clear();
-            running = true;
+            searching = None;
+            print_found(&result);
+            rng.reseed(result.seed);
+            world.load(Body::new_random_bodies(
+                args.bodies,
+                &world.config,
+                &mut rng,
+            ));
+            found = Some(result);
         }
 
-        // Toggle UI on U.
+        // Spawn a new body where the mouse is pressed, launching it along the
+        // drag on release, or delete the body under the mouse on ctrl+click,
+        // along with its trails on ctrl+shift+click.
+        let mouse_world = view.camera.screen_to_world(mouse_position().into());
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if !ctrl {
+                spawn_start = Some(mouse_world);
+            } else if let Some(idx) = body_at(mouse_world, &world.bodies) {
+                world.remove_body(world.bodies[idx].id, shift);
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            if let Some(position) = spawn_start.take() {
+                let id = world.new_id();
+                world.bodies.push(Body {
+                    position,
+                    velocity: (mouse_world - position) / VELOCITY_ARROW_SCALE,
+                    mass: SPAWN_MASS,
+                    radius: world.config.radius(SPAWN_MASS),
+                    ..Body::new_random(id, &world.config, &mut rng)
+                });
+            }
+        }
+
+        // Cycle through the bodies on tab, selecting none after the last one.
+        if is_key_pressed(KeyCode::Tab) {
+            let ids: Vec<usize> = world.bodies.iter().map(|body| body.id).collect();
+            view.selected = match view
+                .selected
+                .and_then(|id| ids.iter().position(|&other| other == id))
+            {
+                Some(idx) => ids.get(idx + 1).copied(),
+                None => ids.first().copied(),
+            };
+        }
+
+        // Nudge the selected body's position with the arrow keys, or its
+        // velocity with shift and the arrow keys.
+        if let Some(body) = view
+            .selected
+            .filter(|_| !ctrl)
+            .and_then(|id| world.bodies.iter_mut().find(|body| body.id == id))
+        {
+            let direction = [
+                (KeyCode::Left, vec2(-1.0, 0.0)),
+                (KeyCode::Right, vec2(1.0, 0.0)),
+                (KeyCode::Up, vec2(0.0, -1.0)),
+                (KeyCode::Down, vec2(0.0, 1.0)),
+            ]
+            .iter()
+            .filter(|(key, _)| is_key_down(*key))
+            .map(|(_, direction)| direction)
+            .sum::<Vec2>();
+            if shift {
+                body.velocity += direction * NUDGE_SPEED;
+            } else {
+                body.position += direction * NUDGE_DISTANCE;
+                // Keep the body within the world even while paused.
+                body.apply_boundary(&world.config);
+            }
+        }
+
+        // Replay the current scenario on backspace, and the last few
+        // hundred steps in slow motion on shift+backspace.
+        if is_key_released(KeyCode::Backspace) {
+            if shift {
+                replay.toggle();
+            } else {
+                world.reset();
+            }
+        }
+
+        // Load presets on number keys.
+        if !shift && is_key_released(KeyCode::Key3) {
+            let bodies = presets::figure_eight(&mut world.config, &mut rng);
+            world.load(bodies);
+        }
+        if !shift && is_key_released(KeyCode::Key4) {
+            world.load(presets::lagrange_triangle(&world.config, &mut rng));
+        }
+        if !shift && is_key_released(KeyCode::Key5) {
+            world.load(presets::binary_star(&world.config, &mut rng));
+        }
+        if !shift && is_key_released(KeyCode::Key6) {
+            world.load(presets::solar_system(&world.config, &mut rng));
+        }
+        if !shift && is_key_released(KeyCode::Key7) {
+            world.load(presets::ring(&world.config, &mut rng));
+        }
+
+        if !shift && is_key_released(KeyCode::Key9) {
+            world.load(presets::hierarchical_triple(&world.config, &mut rng));
+        }
+
+        // Load the restricted three-body preset on 8, or change its launch
+        // velocity with ctrl and the arrow keys, which reloads it.
+        let launch_change = [
+            (KeyCode::Left, vec2(-1.0, 0.0)),
+            (KeyCode::Right, vec2(1.0, 0.0)),
+            (KeyCode::Up, vec2(0.0, -1.0)),
+            (KeyCode::Down, vec2(0.0, 1.0)),
+        ]
+        .iter()
+        .filter(|(key, _)| ctrl && is_key_pressed(*key))
+        .map(|(_, direction)| direction)
+        .sum::<Vec2>()
+            * LAUNCH_STEP;
+        launch += launch_change;
+        if (!shift && is_key_released(KeyCode::Key8)) || launch_change != Vec2::ZERO {
+            let bodies = presets::restricted(&mut world.config, &mut rng, launch);
+            view.selected = bodies.last().map(|body| body.id);
+            world.load(bodies);
+        }
+
+        // Toggle the trails of the first nine bodies on Shift+number keys.
+        if shift {
+            for (key, body) in DIGIT_KEYS.iter().zip(&world.bodies) {
+                if is_key_released(*key) && !view.hidden_trails.remove(&body.id) {
+                    view.hidden_trails.insert(body.id);
+                }
+            }
+        }
+
+        // Export the current state on E.
+        if is_key_released(KeyCode::E) {
+            scenario::export(&world.bodies);
+        }
+
+        // Start/stop recording a GIF on G, which needs a filesystem to save to.
+        if !IS_WASM && !shift && is_key_released(KeyCode::G) {
+            match recording.take() {
+                Some(recording) => recording.save(),
+                None => recording = Some(recording::Recording::new()),
+            }
+        }
+
+        // Take a screenshot on S, once the frame is drawn.
+        let screenshot = !shift && is_key_released(KeyCode::S);
+
+        // Cycle palettes on shift+S, recolouring the current scenario as it
+        // would have been coloured with the new palette.
+        if shift && is_key_released(KeyCode::S) {
+            world.config.palette.toggle();
+            for body in &mut world.initial_bodies {
+                body.colour = world.config.palette.colour(rng.seed(), body.id);
+            }
+            for body in &mut world.bodies {
+                body.colour = match world
+                    .initial_bodies
+                    .iter()
+                    .find(|initial| initial.id == body.id)
+                {
+                    Some(initial) => initial.colour,
+                    None => world.config.palette.colour(rng.seed(), body.id),
+                };
+            }
+        }
+
+        // Toggle the collision banner on Shift+C.
+        if shift && is_key_released(KeyCode::C) {
+            view.collision_banner = !view.collision_banner;
+        }
+
+        // Toggle glowing bodies on Shift+G.
+        if shift && is_key_released(KeyCode::G) {
+            view.glow = !view.glow;
+        }
+
+        // Cycle the UI on U, backwards on shift+U, and hide it straight
+        // away on ctrl+U.
         if is_key_released(KeyCode::U) {
-            show_ui.toggle();
+            if ctrl {
+                view.ui = Ui::Off;
+            } else if shift {
+                view.ui.toggle_back();
+            } else {
+                view.ui.toggle();
+            }
         }
 
-        // Toggle auto-restart on R.
+        // Toggle auto-restart on R, and keeping masses and colours on reset
+        // on shift+R.
         if is_key_released(KeyCode::R) {
-            auto_restart = !auto_restart;
+            if shift {
+                keep_masses = !keep_masses;
+            } else {
+                auto_restart.enabled = !auto_restart.enabled;
+            }
         }
 
-        // Toggle elastic collisions on C.
-        if is_key_released(KeyCode::C) {
-            elastic_collisions = !elastic_collisions;
+        // Cycle collision modes on C.
+        if !shift && is_key_released(KeyCode::C) {
+            world.config.collision_mode.toggle();
         }
 
-        if running {
-            // Calculate forces to apply based on last frame's positions.
-            let mut new_bodies = bodies;
-            new_bodies.iter_mut().for_each(|body| {
-                body.update_velocity(bodies.iter().copied(), elastic_collisions);
-            });
+        // Cycle integrators on I, and toggle adaptive time steps on Shift+I.
+        if !shift && is_key_released(KeyCode::I) {
+            world.config.integrator.toggle();
+        }
+        if shift && is_key_released(KeyCode::I) {
+            world.config.adaptive = !world.config.adaptive;
+        }
 
-            // Update positions based on new velocities.
-            bodies = new_bodies;
-            trails.iter_mut().for_each(|trail| trail.colour.a *= 0.995);
-            trails.extend(bodies.iter().map(Trail::from));
-            while trails.front().map_or(false, |trail| trail.colour.a < 0.01) {
-                trails.pop_front();
+        // Invert gravity on A, and toggle the potential heatmap on shift+A.
+        if is_key_released(KeyCode::A) {
+            if shift {
+                view.potential = !view.potential;
+            } else {
+                world.config.g = -world.config.g;
             }
-            bodies.iter_mut().for_each(Body::update_position);
+        }
 
-            if !elastic_collisions {
-                // If two bodies collide, stop the simulation.
-                running = !has_collision(&bodies);
+        // Halve/double gravity on [/].
+        if is_key_released(KeyCode::LeftBracket) {
+            world.config.g /= 2.0;
+        }
+        if is_key_released(KeyCode::RightBracket) {
+            world.config.g *= 2.0;
+        }
+
+        // Slow down/speed up on -/+.
+        if is_key_released(KeyCode::Minus) || is_key_released(KeyCode::KpSubtract) {
+            world.config.dt /= 2.0;
+        }
+        if is_key_released(KeyCode::Equal) || is_key_released(KeyCode::KpAdd) {
+            world.config.dt *= 2.0;
+        }
+
+        // Decrease/increase softening on ;/', and damping on shift+;/'.
+        if !shift && is_key_released(KeyCode::Semicolon) {
+            world.config.softening = (world.config.softening - 1.0).max(0.0);
+        }
+        if !shift && is_key_released(KeyCode::Apostrophe) {
+            world.config.softening += 1.0;
+        }
+        // Damping moves in whole steps, so it goes back to exactly zero.
+        let damping_steps = (world.config.damping / DAMPING_STEP).round();
+        if shift && is_key_released(KeyCode::Semicolon) {
+            world.config.damping = (damping_steps - 1.0).max(0.0) * DAMPING_STEP;
+        }
+        if shift && is_key_released(KeyCode::Apostrophe) {
+            world.config.damping = (damping_steps + 1.0) * DAMPING_STEP;
+        }
+
+        // Decrease/increase restitution on N/M.
+        if is_key_released(KeyCode::N) {
+            world.config.restitution = (world.config.restitution - 0.1).max(0.0);
+        }
+        // Toggle collision sounds on shift+M.
+        if is_key_released(KeyCode::M) {
+            if shift {
+                sounds.toggle();
+            } else {
+                world.config.restitution = (world.config.restitution + 0.1).min(1.0);
             }
         }
 
-        // Draw all bodies & trails.
-        clear_background(BLACK);
-        bodies.iter().for_each(Body::draw);
-        trails.iter().for_each(Trail::draw);
-        draw_ui(&bodies, show_ui, auto_restart, running, elastic_collisions);
+        // Toggle velocity arrows on V, and tidal stretching on shift+V.
+        if is_key_released(KeyCode::V) {
+            if shift {
+                view.tides = !view.tides;
+            } else {
+                view.velocities = !view.velocities;
+            }
+        }
+
+        // Tighten/loosen the tolerance for returning to the initial state on
+        // J/Shift+J.
+        if is_key_released(KeyCode::J) {
+            if shift {
+                world.config.period_tolerance *= 2.0;
+            } else {
+                world.config.period_tolerance /= 2.0;
+            }
+        }
+
+        // Toggle stopping when a body escapes on Q, and cycle what ends a
+        // scenario for auto-restart on shift+Q.
+        if is_key_released(KeyCode::Q) {
+            if shift {
+                auto_restart.on.toggle();
+            } else {
+                world.config.stop_on_ejection = !world.config.stop_on_ejection;
+            }
+        }
+
+        // Cycle trail colours on Y, and toggle accumulating trails in a
+        // texture on shift+Y.
+        if is_key_released(KeyCode::Y) {
+            if shift {
+                view.accumulate = !view.accumulate;
+            } else {
+                view.trail_colour.toggle();
+            }
+        }
+
+        // Toggle the centre of mass marker on B, and distances between
+        // bodies on shift+B.
+        if is_key_released(KeyCode::B) {
+            if shift {
+                view.distances = !view.distances;
+            } else {
+                view.barycentre = !view.barycentre;
+            }
+        }
+
+        // Toggle the grid on H, and hiding bodies to show only their trails
+        // on shift+H.
+        if is_key_released(KeyCode::H) {
+            if shift {
+                view.bodies = !view.bodies;
+            } else {
+                view.grid = !view.grid;
+            }
+        }
+
+        // Toggle predicted paths on O, and the countdown to the next
+        // collision on shift+O.
+        if is_key_released(KeyCode::O) {
+            if shift {
+                view.countdown = !view.countdown;
+            } else {
+                view.prediction = !view.prediction;
+            }
+        }
+
+        // Toggle force arrows on F, and freezing trails on shift+F.
+        if is_key_released(KeyCode::F) {
+            if shift {
+                world.config.freeze_trails = !world.config.freeze_trails;
+            } else {
+                view.forces = !view.forces;
+            }
+        }
+
+        // Shorten/lengthen trails on K/L.
+        if !shift && is_key_released(KeyCode::K) {
+            world.config.trail_fade = (1.0 - (1.0 - world.config.trail_fade) * 2.0).max(0.5);
+        }
+        if !shift && is_key_released(KeyCode::L) {
+            world.config.trail_fade = (1.0 - (1.0 - world.config.trail_fade) / 2.0).min(0.9999);
+        }
+
+        // Halve/double the maximum number of trail points on Shift+K/L.
+        if shift && is_key_released(KeyCode::K) {
+            world.config.max_trails = (world.config.max_trails / 2).max(MIN_MAX_TRAILS);
+        }
+        if shift && is_key_released(KeyCode::L) {
+            world.config.max_trails = (world.config.max_trails * 2).min(MAX_MAX_TRAILS);
+        }
+
+        // Toggle the centre of mass tracking camera on T. The centre of mass of
+        // bodies wrapping around the screen edges is meaningless, so this opens
+        // up the boundary if it wraps.
+        if is_key_released(KeyCode::T) {
+            view.tracking = !view.tracking;
+            if view.tracking && world.config.boundary == Boundary::Wrap {
+                world.config.set_boundary(Boundary::Open);
+            }
+        }
+
+        // Cycle boundaries on W, and toggle gravity wrapping around the edges
+        // independently on Shift+W.
+        if !shift && is_key_released(KeyCode::W) {
+            let mut boundary = world.config.boundary;
+            boundary.toggle();
+            world.config.set_boundary(boundary);
+        }
+        if shift && is_key_released(KeyCode::W) {
+            world.config.wrap_gravity = !world.config.wrap_gravity;
+        }
+
+        // Scale the mass of the body under the cursor with the scroll wheel, or
+        // zoom towards the cursor if there is none.
+        let (_, scroll) = mouse_wheel();
+        if scroll != 0.0 {
+            let factor = 1.1_f32.powf(scroll.signum());
+            match body_at(mouse_world, &world.bodies) {
+                Some(idx) => {
+                    let mass = (world.bodies[idx].mass * factor).clamp(MIN_MASS, MAX_MASS);
+                    world.bodies[idx].set_mass(mass, &world.config);
+                }
+                None => view.zoom_at(&world.bodies, mouse_position().into(), factor),
+            }
+        }
+
+        // Pan by dragging with the right mouse button.
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_down(MouseButton::Right) {
+            view.pan += (view.last_mouse - mouse) / view.zoom;
+        }
+        view.last_mouse = mouse;
+
+        // Reset zoom and pan on 0.
+        if is_key_released(KeyCode::Key0) {
+            view.zoom = 1.0;
+            view.pan = Vec2::ZERO;
+        }
+
+        // Toggle the force law on shift+D.
+        if shift && is_key_released(KeyCode::D) {
+            world.config.force_law.toggle();
+        }
+
+        // Cycle how radius follows mass on D, and halve/double density on Z/X.
+        let old_radius_mode = (world.config.radius_mode, world.config.density);
+        if !shift && is_key_released(KeyCode::D) {
+            world.config.radius_mode.toggle();
+        }
+        if is_key_released(KeyCode::Z) {
+            world.config.density /= 2.0;
+        }
+        if is_key_released(KeyCode::X) {
+            world.config.density *= 2.0;
+        }
+        if (world.config.radius_mode, world.config.density) != old_radius_mode {
+            for body in world.bodies.iter_mut() {
+                body.radius = world.config.radius(body.mass);
+            }
+        }
+
+        // Slow down/fast forward time on </>. Time is sped up by taking more
+        // steps per frame, rather than longer ones, to keep the integration
+        // stable.
+        while let Some(char) = get_char_pressed() {
+            match char {
+                '<' => time_scale = (time_scale / 2.0).max(MIN_TIME_SCALE),
+                '>' => time_scale = (time_scale * 2.0).min(MAX_TIME_SCALE),
+                _ => {}
+            }
+        }
+
+        // Toggle pause on P, and holding the selected body in place on
+        // shift+P.
+        if is_key_released(KeyCode::P) {
+            if !shift {
+                paused = !paused;
+            } else if let Some(body) = view
+                .selected
+                .and_then(|id| world.bodies.iter_mut().find(|body| body.id == id))
+            {
+                body.toggle_fixed();
+            }
+        }
+
+        let (steps, dt) = if replay.playing() {
+            (0, 0.0)
+        } else if !world.stopped() && !paused {
+            // Take a step each time the time scale adds up to a whole one.
+            step_budget += time_scale;
+            let steps = step_budget.floor();
+            step_budget -= steps;
+            // Scale the step by the frame time, so the speed of the simulation
+            // does not depend on the frame rate.
+            let dt = world.config.dt * get_frame_time().min(MAX_FRAME_TIME) * TARGET_FPS;
+            (steps as u32, dt)
+        } else if !world.stopped() && !shift && is_key_released(KeyCode::Period) {
+            // Step through one frame at a time while paused.
+            (1, world.config.dt)
+        } else {
+            (0, 0.0)
+        };
+        if steps > 0 {
+            let momentum = total_momentum(&world.bodies);
+            for _ in 0..steps {
+                world.step(dt);
+                replay.record(&world);
+                if let Some(Err(err)) = trajectory.as_mut().map(|t| t.record(&world.bodies)) {
+                    eprintln!("Failed to write trajectory, stopping: {err}");
+                    trajectory = None;
+                }
+            }
+            momentum_drifted =
+                (total_momentum(&world.bodies) - momentum).length() > MOMENTUM_TOLERANCE;
+        }
+
+        // Sound the hardest of this frame's collisions.
+        if let Some(impact) = world
+            .effects
+            .iter()
+            .filter(|effect| effect.age == 0)
+            .map(|effect| effect.impact)
+            .reduce(f32::max)
+        {
+            sounds.collide(impact);
+        }
+        world.age_effects();
+
+        // Draw all bodies & trails, on a background which turns red as the
+        // closest bodies get closer.
+        let tension = 1.0 - min_pairwise_distance(&world.bodies, &world.config) / TENSION_DISTANCE;
+        clear_background(Color {
+            r: args.background.r + tension.clamp(0.0, 1.0) * MAX_TENSION_RED,
+            ..args.background
+        });
+        view.update_camera(
+            replay
+                .shown()
+                .and_then(Iterator::last)
+                .unwrap_or(&world.bodies),
+        );
+        if view.accumulate {
+            let buffer = accumulation.get_or_insert_with(accumulation::Accumulation::new);
+            buffer.update(&world, &view.camera, &view.hidden_trails);
+            set_default_camera();
+            buffer.draw();
+        } else {
+            accumulation = None;
+        }
+        set_camera(&view.camera);
+        if view.potential {
+            draw_potential(&world.bodies, &world.config, &view.camera);
+        }
+        if view.grid {
+            draw_grid(&view.camera);
+        }
+        match replay.shown() {
+            Some(states) => draw_replay(states, &view, &world.config),
+            None => draw_world(&world, &view),
+        }
+        if let Some(start) = spawn_start {
+            draw_arrow(start, mouse_world - start, WHITE, &world.config);
+        }
+        set_default_camera();
+        if matches!(view.ui, Ui::Full) {
+            draw_stats(world.bodies.len(), world.trails.len());
+        }
+        if matches!(view.ui, Ui::Full) {
+            draw_info(&world, rng.seed(), found, momentum_drifted);
+        }
+        draw_ui(
+            &world,
+            &view,
+            auto_restart,
+            keep_masses,
+            paused,
+            time_scale,
+            &sounds,
+        );
+        if replay.playing() {
+            draw_banner("REPLAY", -80.0, WHITE);
+        }
+        if let Some(search) = &searching {
+            draw_banner(
+                &format!("SEARCHING {}/{}", search.progress().tried, search.tries()),
+                80.0,
+                WHITE,
+            );
+        }
+        replay.advance();
+
+        // Count down to the next collision, estimating it again every so
+        // often, and whenever the simulation restarted.
+        if view.countdown {
+            countdown = match countdown {
+                Some((estimate, step, frames))
+                    if frames < COUNTDOWN_FRAMES && step <= world.steps() =>
+                {
+                    Some((estimate, step, frames + 1))
+                }
+                _ => Some((
+                    steps_until_collision(&world.bodies, &world.config, COUNTDOWN_STEPS),
+                    world.steps(),
+                    0,
+                )),
+            };
+            if let Some((estimate, step, _)) = countdown {
+                let remaining =
+                    estimate.map(|steps| steps.saturating_sub((world.steps() - step) as usize));
+                draw_countdown(remaining);
+            }
+        } else {
+            countdown = None;
+        }
+
+        // Capture the frame before drawing the recording indicator, so it
+        // does not end up in the recording.
+        if screenshot {
+            recording::screenshot(rng.seed());
+        }
+        if let Some(mut current) = recording.take() {
+            if current.capture() {
+                recording = Some(current);
+            } else {
+                current.save();
+            }
+        }
+        if recording.is_some() {
+            draw_circle(screen_width() - 60.0, screen_height() - 20.0, 6.0, RED);
+            draw_text(
+                "REC",
+                screen_width() - 48.0,
+                screen_height() - 14.0,
+                20.0,
+                RED,
+            );
+        }
+
+        // Show the state of the selected body below the stats, and of the
+        // body under the cursor on top of everything.
+        if let Some(body) = view
+            .selected
+            .and_then(|id| world.bodies.iter().find(|body| body.id == id))
+        {
+            draw_body_panel(body, &world.config, vec2(screen_width(), 80.0));
+        }
+        let mouse = Vec2::from(mouse_position());
+        if let Some(idx) = body_at(view.camera.screen_to_world(mouse), &world.bodies) {
+            draw_body_panel(&world.bodies[idx], &world.config, mouse);
+        }
 
         next_frame().await
     }
 }
 
-/// Returns true if any two bodies are colliding.
-fn has_collision(bodies: &[Body]) -> bool {
-    for i in 0..bodies.len() {
-        for j in i + 1..bodies.len() {
-            if bodies[i].collides_with(&bodies[j]) {
-                return true;
+/// Runs the simulation for a fixed number of steps without a window, then
+/// prints a summary.
+fn run_headless(args: &Args) {
+    let config = SimConfig {
+        world: args.world,
+        ..args.config
+    };
+    let seed = args.seed.unwrap_or_else(random_seed);
+    let mut rng = Rng::new(match args.search {
+        Some(tries) => find_seed(seed, tries, args.bodies, &config).seed,
+        None => seed,
+    });
+    println!("Seed: {}", rng.seed());
+    let mut bodies = match &args.scenario {
+        Some(path) => scenario::load(path, &config, &mut rng).unwrap_or_else(|err| {
+            eprintln!("Failed to load scenario from {path}: {err}");
+            Body::new_random_bodies(args.bodies, &config, &mut rng)
+        }),
+        None => Body::new_random_bodies(args.bodies, &config, &mut rng),
+    };
+    let mut trajectory = args.record_csv.as_deref().and_then(|path| {
+        trajectory::Trajectory::create(path)
+            .map_err(|err| eprintln!("Failed to create trajectory file {path}: {err}"))
+            .ok()
+    });
+
+    let mut collision = None;
+    let mut min_distance = min_pairwise_distance(&bodies, &config);
+    for n in 1..=args.steps {
+        let running = step(&mut bodies, &config, config.dt);
+        if collision.is_none() && (!running || has_collision(&bodies, &config)) {
+            collision = Some(n);
+        }
+        min_distance = min_distance.min(min_pairwise_distance(&bodies, &config));
+        if let Some(Err(err)) = trajectory.as_mut().map(|t| t.record(&bodies)) {
+            eprintln!("Failed to write trajectory, stopping: {err}");
+            trajectory = None;
+        }
+        if !running {
+            break;
+        }
+    }
+    if let Some(Err(err)) = trajectory.map(trajectory::Trajectory::close) {
+        eprintln!("Failed to write trajectory: {err}");
+    }
+
+    for body in &bodies {
+        println!(
+            "Body {}: position ({:.2}, {:.2}), velocity ({:.2}, {:.2}), mass {:.2}",
+            body.id, body.position.x, body.position.y, body.velocity.x, body.velocity.y, body.mass,
+        );
+    }
+    match collision {
+        Some(n) => println!("Collision: step {n}"),
+        None => println!("Collision: none"),
+    }
+    println!("Minimum distance: {min_distance:.2}");
+}
+
+/// Command line arguments.
+struct Args {
+    /// The number of bodies to simulate.
+    bodies: usize,
+    /// The seed for the first scenario.
+    seed: Option<u64>,
+    /// A scenario file to load the first scenario from.
+    scenario: Option<String>,
+    /// A CSV file to record the trajectories of all bodies to.
+    record_csv: Option<String>,
+    /// Whether to run without a window.
+    headless: bool,
+    /// The number of steps to run for without a window.
+    steps: u32,
+    /// The size of the world without a window.
+    world: Vec2,
+    /// The simulation config, from the settings file if there is one.
+    config: SimConfig,
+    /// How many seeds to search for a long-lived first scenario, if any.
+    search: Option<usize>,
+    /// Whether and when to start a new scenario by itself.
+    auto_restart: AutoRestart,
+    /// Whether to start in fullscreen.
+    fullscreen: bool,
+    /// The text of the banner shown when bodies collide.
+    collision_text: Option<String>,
+    /// The background colour.
+    background: Color,
+}
+
+impl Args {
+    /// Parses the command line arguments, falling back to the settings file
+    /// and then defaults for anything missing or invalid.
+    fn parse() -> Self {
+        let settings = settings::path()
+            .and_then(|path| {
+                settings::load(&path)
+                    .map_err(|err| {
+                        eprintln!("Failed to load settings from {}: {err}", path.display())
+                    })
+                    .ok()
+            })
+            .unwrap_or_default();
+        let mut args = Self {
+            bodies: settings.bodies.unwrap_or(3),
+            seed: settings.seed,
+            scenario: None,
+            record_csv: None,
+            headless: false,
+            steps: 1000,
+            world: SimConfig::default().world,
+            config: settings.config,
+            search: None,
+            auto_restart: AutoRestart {
+                enabled: IS_WASM,
+                on: RestartOn::Either,
+            },
+            fullscreen: false,
+            collision_text: None,
+            background: settings.background.unwrap_or(BLACK),
+        };
+        // On the web, options come from the page's query string instead.
+        let mut iter = std::env::args().skip(1).chain(query::args());
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--bodies" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(bodies) => args.bodies = bodies,
+                    None => eprintln!("--bodies requires a number"),
+                },
+                "--seed" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(seed) => args.seed = Some(seed),
+                    None => eprintln!("--seed requires a number"),
+                },
+                "--scenario" => match iter.next() {
+                    Some(path) => args.scenario = Some(path),
+                    None => eprintln!("--scenario requires a path"),
+                },
+                "--record-csv" => match iter.next() {
+                    Some(path) => args.record_csv = Some(path),
+                    None => eprintln!("--record-csv requires a path"),
+                },
+                "--collision-text" => match iter.next() {
+                    Some(text) => args.collision_text = Some(text),
+                    None => eprintln!("--collision-text requires some text"),
+                },
+                "--background" => match iter.next().map(|hex| settings::parse_colour(&hex)) {
+                    Some(Ok(colour)) => args.background = colour,
+                    Some(Err(err)) => eprintln!("--background: {err}"),
+                    None => eprintln!("--background requires a colour"),
+                },
+                "--palette" => match iter.next().map(|name| name.parse()) {
+                    Some(Ok(palette)) => args.config.palette = palette,
+                    Some(Err(err)) => eprintln!("--palette: {err}"),
+                    None => eprintln!("--palette requires random, pastel, neon, grayscale, or id"),
+                },
+                "--g" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(g) => args.config.g = g,
+                    None => eprintln!("--g requires a number"),
+                },
+                "--damping" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(damping) => args.config.damping = damping,
+                    None => eprintln!("--damping requires a number"),
+                },
+                "--dt" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(dt) => args.config.dt = dt,
+                    None => eprintln!("--dt requires a number"),
+                },
+                "--integrator" => match iter.next().map(|name| name.parse()) {
+                    Some(Ok(integrator)) => args.config.integrator = integrator,
+                    Some(Err(err)) => eprintln!("--integrator: {err}"),
+                    None => eprintln!("--integrator requires euler, verlet, or rk4"),
+                },
+                "--min-mass" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(mass) => args.config.min_mass = mass,
+                    None => eprintln!("--min-mass requires a number"),
+                },
+                "--max-mass" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(mass) => args.config.max_mass = mass,
+                    None => eprintln!("--max-mass requires a number"),
+                },
+                "--max-velocity" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(velocity) => args.config.max_velocity = velocity,
+                    None => eprintln!("--max-velocity requires a number"),
+                },
+                "--spawn-margin" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(margin) => args.config.spawn_margin = margin,
+                    None => eprintln!("--spawn-margin requires a number"),
+                },
+                "--units" => args.config.set_units(args.config.units.unwrap_or_default()),
+                "--pixels-per-au" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(pixels_per_au) => args.config.set_units(Units {
+                        pixels_per_au,
+                        ..args.config.units.unwrap_or_default()
+                    }),
+                    None => eprintln!("--pixels-per-au requires a number"),
+                },
+                "--steps-per-year" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(steps_per_year) => args.config.set_units(Units {
+                        steps_per_year,
+                        ..args.config.units.unwrap_or_default()
+                    }),
+                    None => eprintln!("--steps-per-year requires a number"),
+                },
+                "--search" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(tries) => args.search = Some(tries),
+                    None => eprintln!("--search requires a number"),
+                },
+                "--elastic" => args.config.collision_mode = CollisionMode::Elastic,
+                "--auto-restart" => args.auto_restart.enabled = true,
+                "--restart-on" => match iter.next().map(|name| name.parse()) {
+                    Some(Ok(on)) => {
+                        args.auto_restart = AutoRestart { enabled: true, on };
+                    }
+                    Some(Err(err)) => eprintln!("--restart-on: {err}"),
+                    None => eprintln!("--restart-on requires collision, ejection, or either"),
+                },
+                "--fullscreen" => args.fullscreen = true,
+                "--barnes-hut" => args.config.barnes_hut = true,
+                "--headless" => args.headless = true,
+                "--steps" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(steps) => args.steps = steps,
+                    None => eprintln!("--steps requires a number"),
+                },
+                "--width" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(width) => args.world.x = width,
+                    None => eprintln!("--width requires a number"),
+                },
+                "--height" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(height) => args.world.y = height,
+                    None => eprintln!("--height requires a number"),
+                },
+                "--help" | "-h" => {
+                    print!("{HELP}");
+                    std::process::exit(0);
+                }
+                _ => eprintln!("Unknown argument: {arg}, see --help"),
             }
         }
+        args
     }
-    false
 }
 
-/// Draws the UI.
-fn draw_ui(
-    bodies: &[Body],
-    show_ui: Ui,
-    auto_restart: bool,
-    running: bool,
-    elastic_collisions: bool,
-) {
-    if !running {
+/// The usage shown by `--help`.
+const HELP: &str = "\
+Usage: three-bodies [OPTIONS]
+
+Options:
+  --bodies <N>             Number of bodies to simulate [default: 3]
+  --seed <SEED>            Seed for the first scenario
+  --search <N>             Start with the longest-lived of N seeds
+  --g <G>                  Gravitational constant [default: 9.81]
+  --dt <DT>                Time step [default: 1]
+  --integrator <NAME>      euler, verlet, or rk4 [default: euler]
+  --damping <K>            Slow bodies down by K times their velocity
+                           [default: 0]
+  --min-mass <MASS>        Smallest mass of random bodies [default: 1]
+  --max-mass <MASS>        Largest mass of random bodies [default: 10]
+  --max-velocity <V>       Largest initial velocity of random bodies [default: 1]
+  --palette <NAME>         random, pastel, neon, grayscale, or id
+                           [default: random]
+  --background <#RRGGBB>   Background colour [default: #000000]
+  --spawn-margin <F>       Fraction of the world left free along each edge
+                           when placing random bodies [default: 0.25]
+  --units                  Show AU, years, and solar masses, scaling G to
+                           match
+  --pixels-per-au <N>      Pixels per AU, implies --units [default: 100]
+  --steps-per-year <N>     Steps per year, implies --units [default: 600]
+  --elastic                Bounce bodies off each other instead of stopping
+  --auto-restart           Start a new scenario after each collision or
+                           ejection
+  --restart-on <WHAT>      collision, ejection, or either, implies
+                           --auto-restart [default: either]
+  --fullscreen             Start in fullscreen
+  --barnes-hut             Approximate gravity for many bodies
+  --scenario <PATH>        Load the first scenario from a JSON file
+  --record-csv <PATH>      Record trajectories to a CSV file
+  --collision-text <TEXT>  Text of the collision banner
+  --headless               Run without a window and print the result
+  --steps <N>              Steps to run for without a window [default: 1000]
+  --width <W>              World width without a window [default: 800]
+  --height <H>             World height without a window [default: 600]
+  -h, --help               Print this help
+
+Defaults can also be set in three-bodies.toml.
+
+Keys:
+  Space             new scenario
+  Enter             search seeds for a long-lived scenario
+  Backspace         replay the scenario from the start
+  Shift+Backspace   replay the last moments in slow motion
+  3-9               presets
+  Ctrl+arrows       change the launch velocity of preset 8
+  Click/drag        spawn/launch a body
+  Ctrl+click        delete a body
+  Ctrl+Shift+click  delete a body and its trails
+  Tab               select the next body
+  Arrows            nudge the selected body
+  Shift+arrows      nudge the selected body's velocity
+  Shift+P           hold the selected body in place
+  Shift+1-9         hide/show a body's trails
+  Scroll            change the mass of a body, or zoom
+  Right drag        pan
+  0                 reset zoom and pan
+  P                 pause
+  .                 step while paused
+  </>               slow down/speed up time
+  -/+               shrink/grow the time step
+  [/]               halve/double gravity
+  A                 invert gravity
+  Shift+A           potential heatmap
+  ;/'               decrease/increase softening
+  Shift+;/'         decrease/increase damping
+  N/M               decrease/increase restitution
+  Shift+M           collision sounds
+  C                 cycle collision modes
+  Shift+C           collision banner
+  I                 cycle integrators
+  Shift+I           adaptive time steps
+  W                 cycle boundaries
+  Shift+W           gravity wrapping around the edges
+  D                 cycle how radius follows mass
+  Shift+D           force law
+  Z/X               halve/double density
+  T                 tracking camera
+  V                 velocity arrows
+  Shift+V           tidal stretching
+  F                 force arrows
+  Shift+F           freeze trails
+  O                 predicted paths
+  Shift+O           countdown to the next collision
+  H                 grid
+  Shift+H           hide bodies, showing only their trails
+  B                 centre of mass
+  Shift+B           distances between bodies
+  Y                 cycle trail colours
+  Shift+Y           accumulated trails
+  K/L               shorten/lengthen trails
+  Shift+K/L         halve/double trail points
+  J/Shift+J         tighten/loosen the period tolerance
+  Q                 stop when a body escapes
+  Shift+Q           cycle what ends a scenario for auto-restart
+  R                 auto-restart
+  Shift+R           keep masses and colours for new scenarios
+  E                 export the scenario
+  G                 record a GIF
+  Shift+G           glowing bodies
+  S                 screenshot
+  Shift+S           cycle palettes
+  U/Shift+U         cycle the UI forwards/backwards
+  Ctrl+U            hide the UI
+  Esc               quit
+";
+
+/// Searches consecutive seeds for the longest-lived scenario, and reports
+/// the best one.
+fn find_seed(first: u64, tries: usize, bodies: usize, config: &SimConfig) -> search::Found {
+    let found = search::search(first, tries, bodies, config, MAX_SEARCH_STEPS);
+    print_found(&found);
+    found
+}
+
+/// Reports the best seed a search found.
+fn print_found(found: &search::Found) {
+    println!(
+        "Best of {} seeds: {}, lasting {} steps",
+        found.tried, found.seed, found.steps
+    );
+}
+
+/// Returns a new seed based on the current time.
+fn random_seed() -> u64 {
+    (miniquad::date::now() * 1_000_000.0) as u64
+}
+
+/// Draws all bodies and trails of a world, and any arrows the view shows.
+fn draw_world(world: &World, view: &View) {
+    if view.barycentre {
+        draw_barycentre(&world.bodies, &world.config);
+    }
+    if view.prediction {
+        draw_prediction(&world.bodies, &world.config);
+    }
+    if view.bodies {
+        for body in &world.bodies {
+            draw_body(body, view.glow);
+            if view.selected == Some(body.id) {
+                draw_circle_lines(
+                    body.position.x,
+                    body.position.y,
+                    body.radius + 4.0,
+                    2.0,
+                    WHITE,
+                );
+            }
+        }
+    }
+    if !view.accumulate {
+        draw_trails(&world.trails, view.trail_colour, &view.hidden_trails);
+    }
+    draw_effects(&world.effects);
+    if view.velocities {
+        draw_velocities(&world.bodies, &world.config);
+    }
+    if view.forces {
+        draw_forces(&world.bodies, &world.config);
+    }
+    if view.tides {
+        draw_tides(&world.bodies, &world.config);
+    }
+}
+
+/// Draws a replay up to the state it has reached, with the path each body
+/// took so far and the bodies where they were then.
+fn draw_replay<'a>(states: impl Iterator<Item = &'a Vec<Body>>, view: &View, config: &SimConfig) {
+    let mut last: Option<&Vec<Body>> = None;
+    for state in states {
+        for body in state
+            .iter()
+            .filter(|body| !view.hidden_trails.contains(&body.id))
+        {
+            let Some(before) = last
+                .into_iter()
+                .flatten()
+                .find(|before| before.id == body.id)
+            else {
+                continue;
+            };
+            // Don't draw a line all the way across the world where the body
+            // wrapped around it.
+            let delta = body.position - before.position;
+            if delta.x.abs() > config.world.x / 2.0 || delta.y.abs() > config.world.y / 2.0 {
+                continue;
+            }
+            draw_line(
+                before.position.x,
+                before.position.y,
+                body.position.x,
+                body.position.y,
+                2.0,
+                body.colour,
+            );
+        }
+        last = Some(state);
+    }
+    if view.bodies {
+        for body in last.into_iter().flatten() {
+            draw_body(body, view.glow);
+        }
+    }
+}
+
+/// Draws a dim grid across the part of the world the camera sees, so it
+/// moves and scales with the camera.
+fn draw_grid(camera: &Camera2D) {
+    let colour = Color::new(1.0, 1.0, 1.0, 0.1);
+    let min = camera.screen_to_world(Vec2::ZERO);
+    let max = camera.screen_to_world(vec2(screen_width(), screen_height()));
+    let mut x = (min.x / GRID_SPACING).ceil() * GRID_SPACING;
+    while x <= max.x {
+        draw_line(x, min.y, x, max.y, 1.0, colour);
+        x += GRID_SPACING;
+    }
+    let mut y = (min.y / GRID_SPACING).ceil() * GRID_SPACING;
+    while y <= max.y {
+        draw_line(min.x, y, max.x, y, 1.0, colour);
+        y += GRID_SPACING;
+    }
+}
+
+/// Draws the gravitational potential across the part of the world the camera
+/// sees as a coarse heatmap, deepest where the cells are brightest.
+///
+/// Depths are relative to the average depth on the screen, so the heatmap
+/// shows the shape of the potential whatever its scale. Where gravity
+/// repels, the potential has hills rather than wells, which are not drawn.
+fn draw_potential(bodies: &[Body], config: &SimConfig, camera: &Camera2D) {
+    let columns = (screen_width() / HEATMAP_CELL).ceil() as usize;
+    let rows = (screen_height() / HEATMAP_CELL).ceil() as usize;
+    let min = camera.screen_to_world(Vec2::ZERO);
+    let max = camera.screen_to_world(vec2(
+        columns as f32 * HEATMAP_CELL,
+        rows as f32 * HEATMAP_CELL,
+    ));
+    let cell = (max - min) / vec2(columns as f32, rows as f32);
+    let depths: Vec<(Vec2, f32)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| vec2(column as f32, row as f32)))
+        .map(|idx| {
+            let corner = min + idx * cell;
+            let depth = -potential_at(corner + cell / 2.0, bodies, config);
+            (corner, depth.max(0.0))
+        })
+        .collect();
+    let average = depths.iter().map(|(_, depth)| depth).sum::<f32>() / depths.len() as f32;
+    if average <= 0.0 {
+        return;
+    }
+    for (corner, depth) in depths {
+        let t = depth / (depth + average);
+        draw_rectangle(
+            corner.x,
+            corner.y,
+            cell.x,
+            cell.y,
+            Color::new(0.3 * t, 0.1 * t, t, 0.4 * t),
+        );
+    }
+}
+
+/// Draws a cross at the centre of mass of all bodies.
+///
+/// The centre of mass is ambiguous if bodies wrap around the edges of the
+/// world, so nothing is drawn then.
+fn draw_barycentre(bodies: &[Body], config: &SimConfig) {
+    if config.boundary == Boundary::Wrap {
+        return;
+    }
+    if let Some(centre) = centre_of_mass(bodies) {
+        draw_line(
+            centre.x - 6.0,
+            centre.y,
+            centre.x + 6.0,
+            centre.y,
+            1.0,
+            WHITE,
+        );
+        draw_line(
+            centre.x,
+            centre.y - 6.0,
+            centre.x,
+            centre.y + 6.0,
+            1.0,
+            WHITE,
+        );
+        draw_circle_lines(centre.x, centre.y, 4.0, 1.0, WHITE);
+    }
+}
+
+/// Draws the predicted path of each body as dots, fading out with time.
+fn draw_prediction(bodies: &[Body], config: &SimConfig) {
+    let states = predict(bodies, config, PREDICTION_STEPS);
+    for (idx, state) in states.iter().enumerate().step_by(PREDICTION_DOT_INTERVAL) {
+        let alpha = 0.5 * (1.0 - idx as f32 / PREDICTION_STEPS as f32);
+        for body in state {
+            let colour = Color {
+                a: alpha,
+                ..body.colour
+            };
+            draw_circle(body.position.x, body.position.y, 1.0, colour);
+        }
+    }
+}
+
+/// Draws a body.
+///
+/// Glowing bodies are surrounded by layers of translucent circles, which add
+/// up to a glow fading out with the distance from the body.
+fn draw_body(body: &Body, glow: bool) {
+    if glow {
+        for layer in 0..GLOW_LAYERS {
+            let fraction = layer as f32 / GLOW_LAYERS as f32;
+            let radius = body.radius * (1.0 + (GLOW_SCALE - 1.0) * (1.0 - fraction));
+            let colour = Color {
+                a: 0.6 / GLOW_LAYERS as f32,
+                ..body.colour
+            };
+            draw_circle(body.position.x, body.position.y, radius, colour);
+        }
+    }
+    draw_circle(body.position.x, body.position.y, body.radius, body.colour);
+    if body.fixed {
+        let size = body.radius + 3.0;
+        draw_rectangle_lines(
+            body.position.x - size,
+            body.position.y - size,
+            size * 2.0,
+            size * 2.0,
+            2.0,
+            LIGHTGRAY,
+        );
+    }
+}
+
+/// Draws collision flashes as rings which expand and fade out as they age.
+fn draw_effects(effects: &[Effect]) {
+    for effect in effects {
+        let progress = effect.age as f32 / EFFECT_FRAMES as f32;
+        draw_circle_lines(
+            effect.position.x,
+            effect.position.y,
+            effect.radius + progress * EFFECT_GROWTH,
+            2.0,
+            Color::new(1.0, 1.0, 1.0, 1.0 - progress),
+        );
+    }
+}
+
+/// Draws all trails, connecting consecutive points left by the same body.
+fn draw_trails(trails: &VecDeque<Trail>, colours: TrailColour, hidden: &HashSet<usize>) {
+    let mut previous: HashMap<usize, &Trail> = HashMap::new();
+    for trail in trails
+        .iter()
+        .filter(|trail| !hidden.contains(&trail.body_id))
+    {
+        if let Some(last) = previous.insert(trail.body_id, trail) {
+            draw_trail(last, trail, colours);
+        }
+    }
+}
+
+/// Draws a line from a trail point to the next one left by the same body.
+fn draw_trail(trail: &Trail, next: &Trail, colours: TrailColour) {
+    // Don't draw a line all the way across the screen where the body
+    // wrapped around it.
+    let delta = next.position - trail.position;
+    if delta.x.abs() > screen_width() / 2.0 || delta.y.abs() > screen_height() / 2.0 {
+        return;
+    }
+    let (colour, width) = match colours {
+        TrailColour::Body => (next.colour, 2.0),
+        TrailColour::Speed => {
+            // Blue for slow, red for fast.
+            let t = next.speed / (next.speed + TRAIL_SPEED_SCALE);
+            (Color::new(t, 0.2, 1.0 - t, next.colour.a), 2.0)
+        }
+        TrailColour::Nearest => {
+            // Blue for far away, glowing yellow for near misses.
+            let t = TRAIL_DISTANCE_SCALE / (next.nearest + TRAIL_DISTANCE_SCALE);
+            (Color::new(t, t * t, 1.0 - t, next.colour.a), 2.0)
+        }
+        TrailColour::Comet => {
+            // Faint and thin for slow, bright white and wide for fast.
+            let t = next.speed / (next.speed + TRAIL_SPEED_SCALE);
+            let colour = Color::new(
+                next.colour.r + (1.0 - next.colour.r) * t,
+                next.colour.g + (1.0 - next.colour.g) * t,
+                next.colour.b + (1.0 - next.colour.b) * t,
+                next.colour.a * (0.3 + 0.7 * t),
+            );
+            (colour, 1.0 + COMET_WIDTH * t * t)
+        }
+    };
+    draw_line(
+        trail.position.x,
+        trail.position.y,
+        next.position.x,
+        next.position.y,
+        width,
+        colour,
+    );
+}
+
+/// Draws an arrow along each body's velocity.
+fn draw_velocities(bodies: &[Body], config: &SimConfig) {
+    for body in bodies {
+        draw_arrow(
+            body.position,
+            body.velocity * VELOCITY_ARROW_SCALE,
+            body.colour,
+            config,
+        );
+    }
+}
+
+/// Draws an arrow along the net force on each body.
+fn draw_forces(bodies: &[Body], config: &SimConfig) {
+    for body in bodies {
+        let force = body.acceleration(bodies, config) * body.mass;
+        draw_arrow(
+            body.position,
+            (force * FORCE_ARROW_SCALE).clamp_length_max(MAX_FORCE_ARROW_LENGTH),
+            WHITE,
+            config,
+        );
+    }
+}
+
+/// Draws faint lines beyond the edges of each body, as long as the tidal
+/// force stretches it.
+///
+/// The stretch is the difference between the acceleration of the near and far
+/// edges of the body, along the direction of the acceleration of its centre,
+/// which points at whichever body pulls it the hardest during close
+/// encounters.
+fn draw_tides(bodies: &[Body], config: &SimConfig) {
+    for body in bodies {
+        let Some(axis) = body.acceleration(bodies, config).try_normalize() else {
+            continue;
+        };
+        let edge = axis * body.radius;
+        let near = Body {
+            position: body.position + edge,
+            ..*body
+        };
+        let far = Body {
+            position: body.position - edge,
+            ..*body
+        };
+        let difference = near.acceleration(bodies, config) - far.acceleration(bodies, config);
+        let stretch = (difference.dot(axis) * TIDE_SCALE).min(MAX_TIDE_LENGTH);
+        if stretch <= 0.0 {
+            continue;
+        }
+        let colour = Color {
+            a: 0.4,
+            ..body.colour
+        };
+        for edge in [edge, -edge] {
+            let start = body.position + edge;
+            let end = start + edge.normalize() * stretch;
+            draw_line(start.x, start.y, end.x, end.y, 1.0, colour);
+        }
+    }
+}
+
+/// Labels the midpoint of each pair of bodies with the distance between them,
+/// and how many times their collision distance that is, turning red as they
+/// close in on each other.
+fn draw_distances(bodies: &[Body], config: &SimConfig, camera: &Camera2D) {
+    if bodies.len() > MAX_DISTANCE_BODIES {
+        return;
+    }
+    for (a, b) in pairs(bodies) {
+        let delta = a.delta_to(b, config);
+        let distance = delta.length();
+        let ratio = distance / (a.radius + b.radius);
+        let closeness = (1.0 / ratio).clamp(0.0, 1.0);
+        let position = camera.world_to_screen(a.position + delta / 2.0);
         draw_text(
-            "COLLISION",
-            screen_width() / 2.0 - 64.0, // NB Manually centred.
-            screen_height() / 2.0,
-            32.0,
+            &format!("{} ({ratio:.2}x)", format_length(distance, config)),
+            position.x,
+            position.y,
+            14.0,
+            Color::new(1.0, 1.0 - closeness, 1.0 - closeness, 0.6 + 0.4 * closeness),
+        );
+    }
+}
+
+/// Draws an arrow from a point along a vector.
+///
+/// If the arrow leaves the screen while the screen wraps, it is also drawn
+/// shifted by the screen size, so it wraps around the edges like the bodies
+/// do.
+fn draw_arrow(start: Vec2, vector: Vec2, colour: Color, config: &SimConfig) {
+    let length = vector.length();
+    if length <= f32::EPSILON {
+        return;
+    }
+    let end = start + vector;
+    let head = vector / length * length.min(8.0);
+    let wraps = config.boundary == Boundary::Wrap
+        && (end.x < 0.0 || end.x > config.world.x || end.y < 0.0 || end.y > config.world.y);
+    let shifts: &[f32] = if wraps { &[-1.0, 0.0, 1.0] } else { &[0.0] };
+    for x in shifts {
+        for y in shifts {
+            let offset = vec2(*x, *y) * config.world;
+            let (start, end) = (start + offset, end + offset);
+            draw_line(start.x, start.y, end.x, end.y, 1.0, colour);
+            draw_triangle(
+                end,
+                end - head + head.perp() * 0.5,
+                end - head - head.perp() * 0.5,
+                colour,
+            );
+        }
+    }
+}
+
+/// Draws performance statistics in the top right corner.
+fn draw_stats(bodies: usize, trails: usize) {
+    let stats = [
+        format!("{} fps", get_fps()),
+        format!("{:.1} ms", get_frame_time() * 1000.0),
+        format!("{bodies} bodies"),
+        format!("{trails} trail points"),
+    ];
+    for (idx, stat) in stats.iter().enumerate() {
+        let width = measure_text(stat, None, 16, 1.0).width;
+        draw_text(
+            stat,
+            screen_width() - 10.0 - width,
+            20.0 + idx as f32 * 14.0,
+            16.0,
             WHITE,
         );
     }
+}
+
+/// Draws information about the state of the simulation in the top left
+/// corner.
+///
+/// The momentum is highlighted if it changed during the last step, which
+/// should not happen, as gravity only acts between the bodies.
+///
+/// If the seed was found by searching, it shows how long it lasted.
+fn draw_info(world: &World, seed: u64, found: Option<search::Found>, momentum_drifted: bool) {
+    let (kinetic, potential, total) = total_energy(&world.bodies, &world.config);
+    let momentum = total_momentum(&world.bodies).length();
+    let info = [
+        (
+            match found.filter(|found| found.seed == seed) {
+                Some(found) => format!(
+                    "seed {seed} (best of {}, lasting {} steps)",
+                    found.tried, found.steps
+                ),
+                None => format!("seed {seed}"),
+            },
+            WHITE,
+        ),
+        (format!("kinetic energy {kinetic:.2}"), WHITE),
+        (format!("potential energy {potential:.2}"), WHITE),
+        (format!("total energy {total:.2}"), WHITE),
+        (
+            format!("momentum {momentum:.2}"),
+            if momentum_drifted { RED } else { WHITE },
+        ),
+        angular_momentum_line(world),
+        (format!("collisions {}", world.collisions()), WHITE),
+        (
+            format!("dissipated energy {:.2}", world.dissipated()),
+            WHITE,
+        ),
+        (
+            format!(
+                "min distance {}",
+                format_length(world.min_distance(), &world.config)
+            ),
+            WHITE,
+        ),
+        (format!("sub-steps {}", world.substeps()), WHITE),
+        (
+            match world.period() {
+                Some(period) => format!("period {}", format_time(period, &world.config)),
+                None => "period none".to_string(),
+            },
+            WHITE,
+        ),
+        (
+            format!("elapsed {}", format_time(world.steps(), &world.config)),
+            WHITE,
+        ),
+    ];
+    for (idx, (line, colour)) in info.iter().enumerate() {
+        draw_text(line, 10.0, 20.0 + idx as f32 * 14.0, 16.0, *colour);
+    }
+}
+
+/// Returns the info line on the total angular momentum, which is red if it
+/// drifted from its initial value.
+///
+/// Angular momentum is not well defined if the world wraps around, as each
+/// body is in many places at once.
+fn angular_momentum_line(world: &World) -> (String, Color) {
+    if world.config.boundary == Boundary::Wrap {
+        return ("angular momentum n/a while wrapping".to_string(), GRAY);
+    }
+    let (Some(initial), Some(current)) = (
+        angular_momentum(&world.initial_bodies),
+        angular_momentum(&world.bodies),
+    ) else {
+        return ("angular momentum none".to_string(), WHITE);
+    };
+    let drift = (current - initial).abs() / initial.abs().max(1.0);
+    (
+        format!(
+            "angular momentum {current:.2} (drift {:.2}%)",
+            drift * 100.0
+        ),
+        if drift > ANGULAR_MOMENTUM_TOLERANCE {
+            RED
+        } else {
+            WHITE
+        },
+    )
+}
+
+/// Formats a mass, in solar masses with units.
+fn format_mass(mass: f32, config: &SimConfig) -> String {
+    match config.units {
+        Some(_) => format!("{mass:.2} solar masses"),
+        None => format!("{mass:.2}"),
+    }
+}
+
+/// Formats a length in pixels, or in AU with units.
+fn format_length(pixels: f32, config: &SimConfig) -> String {
+    match config.units {
+        Some(units) => format!("{:.2} AU", units.length(pixels)),
+        None => format!("{pixels:.1}"),
+    }
+}
+
+/// Formats a speed in pixels per step, or in AU per year with units.
+fn format_speed(speed: f32, config: &SimConfig) -> String {
+    match config.units {
+        Some(units) => format!("{:.2} AU/year", units.speed(speed)),
+        None => format!("{speed:.2}"),
+    }
+}
+
+/// Formats a number of steps, as years with units.
+fn format_time(steps: u32, config: &SimConfig) -> String {
+    match config.units {
+        Some(units) => format!("{:.2} years", units.time(steps as f32 * config.dt)),
+        None => format!("{steps} steps"),
+    }
+}
+
+/// Draws the UI.
+fn draw_ui(
+    world: &World,
+    view: &View,
+    auto_restart: AutoRestart,
+    keep_masses: bool,
+    paused: bool,
+    time_scale: f32,
+    sounds: &sound::Sounds,
+) {
+    let config = &world.config;
+    if world.collided() && view.collision_banner {
+        draw_banner(&view.collision_text, 0.0, WHITE);
+    }
+
+    if world.ejected().is_some() {
+        draw_banner("EJECTED", -40.0, WHITE);
+    }
+
+    // Flash the period when the bodies return to their initial state.
+    if let Some(period) = world.period() {
+        let since = world.steps_since_return();
+        if since < PERIOD_FLASH_STEPS {
+            let alpha = 1.0 - since as f32 / PERIOD_FLASH_STEPS as f32;
+            draw_banner(
+                &format!("PERIOD {period}"),
+                40.0,
+                Color::new(1.0, 1.0, 1.0, alpha),
+            );
+        }
+    }
+
+    if view.distances && matches!(view.ui, Ui::Full | Ui::Minimal) {
+        draw_distances(&world.bodies, config, &view.camera);
+    }
 
     // Body info
-    if matches!(show_ui, Ui::Full | Ui::Minimal) {
-        for body in bodies {
+    if view.bodies && matches!(view.ui, Ui::Full | Ui::Minimal) {
+        for body in &world.bodies {
+            let position = view.camera.world_to_screen(body.position);
             draw_text(
                 &format!("m {:.2}", body.mass),
-                body.position.x + 10.0,
-                body.position.y + 10.0,
+                position.x + 10.0,
+                position.y + 10.0,
                 16.0,
                 body.colour,
             );
             draw_text(
                 &format!("v {:.2}", body.velocity.length()),
-                body.position.x + 10.0,
-                body.position.y + 20.0,
+                position.x + 10.0,
+                position.y + 20.0,
                 16.0,
                 body.colour,
             );
@@ -139,181 +1867,497 @@ fn draw_ui(
     }
 
     // Instructions
-    if matches!(show_ui, Ui::Full) {
+    if matches!(view.ui, Ui::Full) {
+        let hidden: Vec<String> = world
+            .bodies
+            .iter()
+            .take(DIGIT_KEYS.len())
+            .enumerate()
+            .filter(|(_, body)| view.hidden_trails.contains(&body.id))
+            .map(|(idx, _)| (idx + 1).to_string())
+            .collect();
+        let hidden = if hidden.is_empty() {
+            "none".to_string()
+        } else {
+            hidden.join(", ")
+        };
         let instructions = [
-            "[SPACE/CLICK/TAP] reset",
-            "[U] toggle UI",
+            "[SPACE] reset",
+            "[CLICK/DRAG] spawn/launch body",
+            "[CTRL+CLICK/CTRL+SHIFT+CLICK] delete body/and its trails",
+            "[BACKSPACE] replay",
+            "[SHIFT+BACKSPACE] replay the last steps in slow motion",
+            "[ENTER] search for a long-lived scenario",
+            "[E] export scenario",
+            "[G] start/stop recording GIF",
+            &format!(
+                "[SHIFT+G] toggle glowing bodies ({})",
+                if view.glow { "on" } else { "off" }
+            ),
+            "[S] screenshot",
+            &format!("[SHIFT+S] cycle palettes ({})", config.palette),
+            "[3] figure-eight preset",
+            "[4] Lagrange triangle preset",
+            "[5/6/7] binary star/solar system/ring presets",
+            "[8] restricted three-body preset",
+            "[9] hierarchical triple preset",
+            "[CTRL+ARROWS] change restricted launch velocity",
+            "[U/SHIFT+U] cycle UI forwards/backwards, [CTRL+U] hide UI",
             &format!(
                 "[R] toggle auto-restart ({})",
-                if auto_restart { "on" } else { "off" }
+                if auto_restart.enabled { "on" } else { "off" }
+            ),
+            &format!("[SHIFT+Q] cycle auto-restart on ({})", auto_restart.on),
+            &format!(
+                "[SHIFT+R] keep masses and colours on reset ({})",
+                if keep_masses { "on" } else { "off" }
+            ),
+            &format!("[P] pause ({})", if paused { "on" } else { "off" }),
+            "[.] step while paused",
+            &format!(
+                "[V] toggle velocity arrows ({})",
+                if view.velocities { "on" } else { "off" }
+            ),
+            &format!(
+                "[SHIFT+V] toggle tidal stretching ({})",
+                if view.tides { "on" } else { "off" }
+            ),
+            &format!(
+                "[F] toggle force arrows ({})",
+                if view.forces { "on" } else { "off" }
+            ),
+            &format!(
+                "[SHIFT+F] freeze trails ({})",
+                if config.freeze_trails { "on" } else { "off" }
+            ),
+            &format!(
+                "[O] toggle predicted paths ({})",
+                if view.prediction { "on" } else { "off" }
+            ),
+            &format!(
+                "[SHIFT+O] toggle collision countdown ({})",
+                if view.countdown { "on" } else { "off" }
+            ),
+            &format!("[H] toggle grid ({})", if view.grid { "on" } else { "off" }),
+            &format!(
+                "[SHIFT+H] toggle bodies, for trails only ({})",
+                if view.bodies { "on" } else { "off" }
+            ),
+            &format!(
+                "[B] toggle centre of mass marker ({})",
+                if view.barycentre { "on" } else { "off" }
+            ),
+            &format!(
+                "[SHIFT+B] toggle distances between bodies ({})",
+                if view.distances { "on" } else { "off" }
+            ),
+            &format!(
+                "[T] toggle tracking camera ({})",
+                if view.tracking { "on" } else { "off" }
+            ),
+            "[SCROLL/RIGHT DRAG/0] zoom/pan/reset view",
+            &format!(
+                "[Q] stop when a body escapes ({})",
+                if config.stop_on_ejection { "on" } else { "off" }
+            ),
+            &format!(
+                "[J/SHIFT+J] tighten/loosen period tolerance ({})",
+                config.period_tolerance
+            ),
+            &format!("[W] cycle boundary ({})", config.boundary),
+            &format!(
+                "[SHIFT+W] toggle gravity wrap ({})",
+                if config.wrap_gravity { "on" } else { "off" }
+            ),
+            "[SCROLL ON BODY] change mass",
+            "[TAB] select body",
+            "[ARROWS/SHIFT+ARROWS] nudge selected position/velocity",
+            "[SHIFT+P] fix/release selected body",
+            &format!("[D] cycle radius mode ({})", config.radius_mode),
+            &format!("[SHIFT+D] toggle force law ({})", config.force_law),
+            &format!("[Z/X] halve/double density ({})", config.density),
+            &format!("[C] cycle collision mode ({})", config.collision_mode),
+            &format!(
+                "[SHIFT+C] toggle collision banner ({})",
+                if view.collision_banner { "on" } else { "off" }
             ),
+            &format!("[I] cycle integrator ({})", config.integrator),
             &format!(
-                "[C] toggle elastic collisions ({})",
-                if elastic_collisions { "on" } else { "off" }
+                "[SHIFT+I] toggle adaptive time steps ({})",
+                if config.adaptive { "on" } else { "off" }
+            ),
+            &format!("[[/]] halve/double gravity ({:.2})", config.g),
+            &format!(
+                "[A] invert gravity ({})",
+                if config.g < 0.0 {
+                    "repelling"
+                } else {
+                    "attracting"
+                }
+            ),
+            &format!(
+                "[SHIFT+A] toggle potential heatmap ({})",
+                if view.potential { "on" } else { "off" }
+            ),
+            &format!("[-/+] slow down/speed up (speed x{})", config.dt),
+            &format!("[</>] slow motion/fast forward ({time_scale}x)"),
+            &format!("[;/'] decrease/increase softening ({})", config.softening),
+            &format!("[SHIFT+;/'] decrease/increase damping ({})", config.damping),
+            &format!(
+                "[N/M] decrease/increase restitution ({:.1})",
+                config.restitution
+            ),
+            &format!(
+                "[SHIFT+M] toggle collision sounds ({})",
+                if !sounds.available() {
+                    "unavailable"
+                } else if sounds.enabled() {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            &format!("[Y] cycle trail colours ({})", view.trail_colour),
+            &format!(
+                "[SHIFT+Y] toggle accumulated trails ({})",
+                if view.accumulate { "on" } else { "off" }
+            ),
+            &format!("[SHIFT+1-9] toggle body trails (hidden: {hidden})"),
+            &format!(
+                "[K/L] shorten/lengthen trails ({} steps)",
+                config.trail_length()
+            ),
+            &format!(
+                "[SHIFT+K/L] halve/double trail points ({} max)",
+                config.max_trails
             ),
         ];
-        instructions
-            .iter()
-            .enumerate()
-            .for_each(|(idx, instruction)| {
+        // Fill columns from the bottom up, stopping short of the information
+        // in the top left corner.
+        let per_column = ((screen_height() - INSTRUCTIONS_TOP) / 14.0).max(1.0) as usize;
+        let mut x = 10.0;
+        for column in instructions.chunks(per_column) {
+            let mut width: f32 = 0.0;
+            for (idx, instruction) in column.iter().enumerate() {
+                width = width.max(measure_text(instruction, None, 16, 1.0).width);
                 draw_text(
                     instruction,
-                    10.0,
+                    x,
                     screen_height() - 14.0 - idx as f32 * 14.0,
                     16.0,
                     WHITE,
-                )
-            });
+                );
+            }
+            x += width + 20.0;
+        }
+    }
+}
+
+/// Draws large text centred on the screen, shifted vertically by an offset.
+fn draw_banner(text: &str, offset: f32, colour: Color) {
+    let size = measure_text(text, None, 32, 1.0);
+    draw_text(
+        text,
+        (screen_width() - size.width) / 2.0,
+        (screen_height() - size.height) / 2.0 + size.offset_y + offset,
+        32.0,
+        colour,
+    );
+}
+
+/// Draws the estimated steps until the next collision at the top of the
+/// screen, redder the closer it is.
+fn draw_countdown(remaining: Option<usize>) {
+    let (text, colour) = match remaining {
+        Some(steps) => {
+            let t = 1.0 - (steps as f32 / COUNTDOWN_STEPS as f32).min(1.0);
+            (
+                format!("collision in ~{steps} steps"),
+                Color::new(1.0, 1.0 - t, 1.0 - t, 1.0),
+            )
+        }
+        None => (format!("no collision within {COUNTDOWN_STEPS} steps"), GRAY),
+    };
+    let size = measure_text(&text, None, 20, 1.0);
+    draw_text(
+        &text,
+        (screen_width() - size.width) / 2.0,
+        24.0,
+        20.0,
+        colour,
+    );
+}
+
+/// Draws the full state of a body in a box next to a screen position, kept on
+/// the screen.
+fn draw_body_panel(body: &Body, config: &SimConfig, near: Vec2) {
+    let lines = [
+        format!("id {}", body.id),
+        format!("mass {}", format_mass(body.mass, config)),
+        format!("speed {}", format_speed(body.velocity.length(), config)),
+        format!(
+            "velocity ({}, {})",
+            format_speed(body.velocity.x, config),
+            format_speed(body.velocity.y, config)
+        ),
+        format!(
+            "position ({}, {})",
+            format_length(body.position.x, config),
+            format_length(body.position.y, config)
+        ),
+        format!("fixed {}", if body.fixed { "yes" } else { "no" }),
+    ];
+    let width = lines
+        .iter()
+        .map(|line| measure_text(line, None, 16, 1.0).width)
+        .fold(0.0, f32::max);
+    let height = lines.len() as f32 * 14.0 + 6.0;
+    // Keep the tooltip on the screen near the edges.
+    let x = (near.x + 16.0).min(screen_width() - width - 12.0);
+    let y = (near.y + 16.0).min(screen_height() - height);
+    draw_rectangle(x, y, width + 12.0, height, Color::new(0.0, 0.0, 0.0, 0.8));
+    draw_rectangle_lines(x, y, width + 12.0, height, 1.0, body.colour);
+    for (idx, line) in lines.iter().enumerate() {
+        draw_text(line, x + 6.0, y + 16.0 + idx as f32 * 14.0, 16.0, WHITE);
+    }
+}
+
+/// What to draw besides the bodies and trails.
+struct View {
+    /// How much of the UI to show.
+    ui: Ui,
+    /// Whether to draw the bodies themselves, rather than only their trails.
+    bodies: bool,
+    /// Whether to draw velocity arrows.
+    velocities: bool,
+    /// Whether to draw how much bodies are stretched by tidal forces.
+    tides: bool,
+    /// Whether to draw force arrows.
+    forces: bool,
+    /// Whether to draw the predicted paths of bodies.
+    prediction: bool,
+    /// Whether to count down the estimated steps until the next collision.
+    countdown: bool,
+    /// Whether to draw a grid behind everything.
+    grid: bool,
+    /// Whether to draw the gravitational potential as a heatmap behind
+    /// everything.
+    potential: bool,
+    /// Whether bodies glow.
+    glow: bool,
+    /// Whether to show a banner when bodies collide.
+    collision_banner: bool,
+    /// The text of the banner shown when bodies collide.
+    collision_text: String,
+    /// Whether to mark the centre of mass.
+    barycentre: bool,
+    /// Whether to label each pair of bodies with the distance between them.
+    distances: bool,
+    /// What trails are coloured by.
+    trail_colour: TrailColour,
+    /// Whether trails are accumulated in a texture rather than drawn from
+    /// stored points.
+    accumulate: bool,
+    /// The ids of bodies whose trails are not drawn.
+    hidden_trails: HashSet<usize>,
+    /// Whether the camera follows the centre of mass.
+    tracking: bool,
+    /// How far the camera is zoomed in.
+    zoom: f32,
+    /// How far the camera is moved from its centre, in world units.
+    pan: Vec2,
+    /// Where the mouse was last frame, for panning.
+    last_mouse: Vec2,
+    /// The camera the simulation is drawn with.
+    camera: Camera2D,
+    /// The id of the body selected for nudging.
+    selected: Option<usize>,
+}
+
+impl View {
+    /// Moves the camera to the centre of mass if tracking, or the centre of
+    /// the screen otherwise, then applies zoom and pan.
+    fn update_camera(&mut self, bodies: &[Body]) {
+        let screen = vec2(screen_width(), screen_height());
+        let centre = if self.tracking {
+            centre_of_mass(bodies).unwrap_or(screen / 2.0)
+        } else {
+            screen / 2.0
+        } + self.pan;
+        let size = screen / self.zoom;
+        let corner = centre - size / 2.0;
+        self.camera = Camera2D::from_display_rect(Rect::new(corner.x, corner.y, size.x, size.y));
+    }
+
+    /// Zooms by a factor, keeping the world position under a screen position
+    /// in place.
+    fn zoom_at(&mut self, bodies: &[Body], point: Vec2, factor: f32) {
+        self.update_camera(bodies);
+        let before = self.camera.screen_to_world(point);
+        self.zoom *= factor;
+        self.update_camera(bodies);
+        self.pan += before - self.camera.screen_to_world(point);
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            ui: Ui::Full,
+            bodies: true,
+            velocities: false,
+            tides: false,
+            forces: false,
+            prediction: false,
+            countdown: false,
+            grid: false,
+            potential: false,
+            glow: false,
+            collision_banner: true,
+            collision_text: "COLLISION".to_string(),
+            barycentre: false,
+            distances: false,
+            trail_colour: TrailColour::Body,
+            accumulate: false,
+            hidden_trails: HashSet::new(),
+            tracking: false,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            last_mouse: Vec2::ZERO,
+            camera: Camera2D::default(),
+            selected: None,
+        }
     }
 }
 
+/// What trails are coloured by.
 #[derive(Clone, Copy)]
-enum Ui {
-    Full,
-    Minimal,
-    Off,
+enum TrailColour {
+    /// The colour of the body which left them.
+    Body,
+    /// The speed of the body when it left them.
+    Speed,
+    /// How close the nearest other body was when they were left.
+    Nearest,
+    /// The speed of the body when it left them, widening and brightening
+    /// them like a comet's tail near close approaches.
+    Comet,
 }
 
-impl Ui {
-    /// Toggles to the next UI state.
+impl TrailColour {
+    /// Toggles to the next trail colour.
     fn toggle(&mut self) {
         *self = match self {
-            Ui::Off => Ui::Minimal,
-            Ui::Minimal => Ui::Full,
-            Ui::Full => Ui::Off,
+            TrailColour::Body => TrailColour::Speed,
+            TrailColour::Speed => TrailColour::Nearest,
+            TrailColour::Nearest => TrailColour::Comet,
+            TrailColour::Comet => TrailColour::Body,
         }
     }
 }
 
-/// A body in the simulation.
-#[derive(Clone, Copy)]
-struct Body {
-    id: usize,
-    colour: Color,
-    position: Vec2,
-    velocity: Vec2,
-    mass: f32,
-}
-
-impl Body {
-    /// Creates a new body with random properties.
-    fn new_random(id: usize) -> Self {
-        let colour = Color::new(
-            rand::gen_range(0.2, 1.0),
-            rand::gen_range(0.2, 1.0),
-            rand::gen_range(0.2, 1.0),
-            1.0,
-        );
-        let position = vec2(
-            rand::gen_range(screen_width() * 0.25, screen_width() * 0.75),
-            rand::gen_range(screen_height() * 0.25, screen_height() * 0.75),
-        );
-        let velocity = vec2(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0));
-        let mass = rand::gen_range(1., 10.);
-        Self {
-            id,
-            colour,
-            position,
-            velocity,
-            mass,
-        }
-    }
-
-    /// Draws the body on the screen.
-    fn draw(&self) {
-        draw_circle(self.position.x, self.position.y, self.mass, self.colour);
-    }
-
-    /// Updates the velocity of the body based on the forces applied by other bodies.
-    fn update_velocity(
-        &mut self,
-        bodies: impl Iterator<Item = Self> + Clone,
-        elastic_collisions: bool,
-    ) {
-        let mut collided = elastic_collisions;
-        if elastic_collisions {
-            self.velocity = bodies
-                .clone()
-                .filter(|&body| body.id != self.id)
-                .filter(|other| self.collides_with(other))
-                .map(|other| {
-                    let m1 = self.mass;
-                    let m2 = other.mass;
-                    let v1 = self.velocity;
-                    let v2 = other.velocity;
-                    let v1_prime = ((m1 - m2) / (m1 + m2)) * v1 + ((2.0 * m2) / (m1 + m2)) * v2;
-                    v1_prime
-                })
-                .reduce(|acc, velocity| acc + velocity)
-                .unwrap_or_else(|| {
-                    collided = false;
-                    self.velocity
-                });
-        }
-        if collided {
-            return;
+impl std::fmt::Display for TrailColour {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrailColour::Body => write!(f, "body"),
+            TrailColour::Speed => write!(f, "speed"),
+            TrailColour::Nearest => write!(f, "nearest body"),
+            TrailColour::Comet => write!(f, "comet tail"),
         }
-        self.velocity += bodies
-            .filter(|&body| body.id != self.id)
-            .map(|other| {
-                let mut delta = other.position - self.position;
-                if delta.x.abs() > screen_width() / 2.0 {
-                    delta.x = delta.x - delta.x.signum() * screen_width();
-                }
+    }
+}
 
-                if delta.y.abs() > screen_height() / 2.0 {
-                    delta.y = delta.y - delta.y.signum() * screen_height();
-                }
-                let distance = delta.length();
-                let direction = delta.normalize();
-                let force = (self.mass * other.mass) / (distance * distance);
-                direction * force
-            })
-            .reduce(|acc, force| acc + force)
-            .map(|force| 9.81 * force / self.mass)
-            .unwrap();
+/// Starting a new scenario by itself once the current one ends.
+#[derive(Clone, Copy)]
+struct AutoRestart {
+    /// Whether to start new scenarios.
+    enabled: bool,
+    /// What ends a scenario.
+    on: RestartOn,
+}
+
+impl AutoRestart {
+    /// Returns whether the scenario in the world ended, and a new one is due.
+    fn due(&self, world: &World) -> bool {
+        let collided = world.collided();
+        let ejected = world.ejected().is_some();
+        self.enabled
+            && match self.on {
+                RestartOn::Collision => collided,
+                RestartOn::Ejection => ejected,
+                RestartOn::Either => collided || ejected,
+            }
     }
+}
+
+/// What ends a scenario for auto-restart.
+///
+/// Only collisions which stop the simulation count, so in other collision
+/// modes only ejections end scenarios.
+#[derive(Clone, Copy)]
+enum RestartOn {
+    Collision,
+    Ejection,
+    Either,
+}
 
-    /// Updates the position of the body based on its velocity.
-    fn update_position(&mut self) {
-        self.position += self.velocity;
-        if self.position.x > screen_width() {
-            self.position.x -= screen_width();
-        } else if self.position.x < 0. {
-            self.position.x += screen_width();
+impl RestartOn {
+    /// Toggles to the next condition.
+    fn toggle(&mut self) {
+        *self = match self {
+            RestartOn::Collision => RestartOn::Ejection,
+            RestartOn::Ejection => RestartOn::Either,
+            RestartOn::Either => RestartOn::Collision,
         }
-        if self.position.y > screen_height() {
-            self.position.y -= screen_height();
-        } else if self.position.y < 0. {
-            self.position.y += screen_height();
+    }
+}
+
+impl std::fmt::Display for RestartOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestartOn::Collision => write!(f, "collision"),
+            RestartOn::Ejection => write!(f, "ejection"),
+            RestartOn::Either => write!(f, "collision or ejection"),
         }
     }
+}
 
-    /// Returns true if this body collides with another.
-    fn collides_with(&self, other: &Self) -> bool {
-        self.position.distance(other.position) <= self.mass + other.mass
+impl std::str::FromStr for RestartOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "collision" => Ok(RestartOn::Collision),
+            "ejection" => Ok(RestartOn::Ejection),
+            "either" => Ok(RestartOn::Either),
+            _ => Err(format!("unknown restart condition '{s}'")),
+        }
     }
 }
 
-/// A trail left behind by a body.
 #[derive(Clone, Copy)]
-struct Trail {
-    position: Vec2,
-    colour: Color,
+enum Ui {
+    Full,
+    Minimal,
+    Off,
 }
 
-impl Trail {
-    /// Draws the trail on the screen.
-    fn draw(&self) {
-        draw_circle(self.position.x, self.position.y, 1.0, self.colour);
+impl Ui {
+    /// Toggles to the next UI state.
+    fn toggle(&mut self) {
+        *self = match self {
+            Ui::Off => Ui::Minimal,
+            Ui::Minimal => Ui::Full,
+            Ui::Full => Ui::Off,
+        }
     }
-}
 
-impl From<&Body> for Trail {
-    fn from(body: &Body) -> Self {
-        Self {
-            position: body.position,
-            colour: body.colour,
+    /// Toggles to the previous UI state.
+    fn toggle_back(&mut self) {
+        *self = match self {
+            Ui::Off => Ui::Full,
+            Ui::Minimal => Ui::Off,
+            Ui::Full => Ui::Minimal,
         }
     }
 }
@@ -0,0 +1,314 @@
+//! Well-known initial conditions.
+
+use macroquad::prelude::*;
+
+use crate::{rng::Rng, total_momentum, Body, SimConfig};
+
+/// The number of steps one period of a periodic preset takes.
+const PERIOD_STEPS: f32 = 600.0;
+
+/// Returns the figure-eight orbit found by Chenciner and Montgomery, where
+/// three equal masses chase each other around a figure eight.
+///
+/// The initial conditions are given for `G = 1` and unit masses, so they are
+/// scaled to the world, and the simulation is configured to match. The scale
+/// keeps all bodies within half a world of each other, so the wrap does not
+/// distort the forces.
+pub fn figure_eight(config: &mut SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const POSITION: Vec2 = Vec2::new(0.970_004_4, -0.243_087_5);
+    const VELOCITY: Vec2 = Vec2::new(-0.932_407_4, -0.864_731_5);
+    const PERIOD: f32 = 6.325_914;
+    const MASS: f32 = 5.0;
+
+    let length = config.world.min_element() * 0.2;
+    let time = PERIOD_STEPS / PERIOD;
+    config.g = length.powi(3) / (time * time * MASS);
+    config.dt = 1.0;
+    config.softening = 0.0;
+
+    let centre = config.world / 2.0;
+    let states = [
+        (POSITION, -VELOCITY / 2.0),
+        (-POSITION, -VELOCITY / 2.0),
+        (Vec2::ZERO, VELOCITY),
+    ];
+    states
+        .iter()
+        .enumerate()
+        .map(|(id, &(position, velocity))| Body {
+            position: centre + position * length,
+            velocity: velocity * length / time,
+            mass: MASS,
+            radius: config.radius(MASS),
+            ..Body::new_random(id, config, rng)
+        })
+        .collect()
+}
+
+/// Returns Lagrange's equilateral triangle solution, where three equal masses
+/// rotate rigidly around their common centre.
+///
+/// The solution is periodic, but unstable, so the triangle eventually breaks
+/// apart. Each body needs a net force towards the centre of
+/// `sqrt(3) * G * m^2 / (s^2 + e^2)` for a side length `s` and softening `e`,
+/// which gives the speed for a circular orbit at the current gravity. There
+/// is no such orbit if gravity repels, so the bodies start at rest then.
+pub fn lagrange_triangle(config: &SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const MASS: f32 = 10.0;
+
+    let radius = config.world.min_element() * 0.25;
+    let side = radius * 3.0_f32.sqrt();
+    let acceleration = 3.0_f32.sqrt() * config.g * MASS / (side * side + config.softening.powi(2));
+    let speed = (acceleration.max(0.0) * radius).sqrt();
+
+    let centre = config.world / 2.0;
+    (0..3)
+        .map(|id| {
+            let direction = Vec2::from_angle(id as f32 * std::f32::consts::TAU / 3.0);
+            Body {
+                position: centre + direction * radius,
+                velocity: direction.perp() * speed,
+                mass: MASS,
+                radius: config.radius(MASS),
+                ..Body::new_random(id, config, rng)
+            }
+        })
+        .collect()
+}
+
+/// Returns a binary star, two heavy bodies orbiting each other closely, with
+/// a light planet orbiting both of them from afar.
+///
+/// The planet sees the binary as a single body of their combined mass.
+pub fn binary_star(config: &SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const STAR_MASS: f32 = 20.0;
+    const PLANET_MASS: f32 = 1.0;
+
+    let separation = config.world.min_element() * 0.06;
+    let star_speed = orbital_speed(STAR_MASS, separation / 2.0, separation, config);
+    let distance = config.world.min_element() * 0.24;
+    let planet_speed = orbital_speed(2.0 * STAR_MASS, distance, distance, config);
+
+    let centre = config.world / 2.0;
+    let states = [
+        (
+            vec2(-separation / 2.0, 0.0),
+            vec2(0.0, star_speed),
+            STAR_MASS,
+        ),
+        (
+            vec2(separation / 2.0, 0.0),
+            vec2(0.0, -star_speed),
+            STAR_MASS,
+        ),
+        (vec2(0.0, -distance), vec2(planet_speed, 0.0), PLANET_MASS),
+    ];
+    let bodies = states
+        .iter()
+        .enumerate()
+        .map(|(id, &(position, velocity, mass))| Body {
+            position: centre + position,
+            velocity,
+            mass,
+            radius: config.radius(mass),
+            ..Body::new_random(id, config, rng)
+        })
+        .collect();
+    at_rest(bodies)
+}
+
+/// Returns a heavy sun with light planets on circular orbits around it,
+/// starting a quarter turn apart from a random angle, so neighbours do not
+/// start out close to each other.
+pub fn solar_system(config: &SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const SUN_MASS: f32 = 100.0;
+    const PLANET_MASS: f32 = 0.1;
+    const ORBITS: [f32; 4] = [0.09, 0.14, 0.19, 0.24];
+
+    let centre = config.world / 2.0;
+    let angle = rng.gen_range(0.0, std::f32::consts::TAU);
+    let sun = Body {
+        position: centre,
+        velocity: Vec2::ZERO,
+        mass: SUN_MASS,
+        radius: config.radius(SUN_MASS),
+        ..Body::new_random(0, config, rng)
+    };
+    let planets = ORBITS.iter().enumerate().map(|(idx, orbit)| {
+        let distance = config.world.min_element() * orbit;
+        let direction = Vec2::from_angle(angle + idx as f32 * std::f32::consts::FRAC_PI_2);
+        Body {
+            position: centre + direction * distance,
+            velocity: direction.perp() * orbital_speed(SUN_MASS, distance, distance, config),
+            mass: PLANET_MASS,
+            radius: config.radius(PLANET_MASS),
+            ..Body::new_random(idx + 1, config, rng)
+        }
+    });
+    let bodies = std::iter::once(sun).chain(planets).collect();
+    at_rest(bodies)
+}
+
+/// Returns a ring of equal masses rotating rigidly around their common
+/// centre.
+///
+/// Like the Lagrange triangle, this is a solution, but an unstable one. Each
+/// body is pulled towards the centre by all others, the one `k` places away
+/// at a distance of `2 r sin(pi k / n)` and an angle whose cosine towards the
+/// centre is `sin(pi k / n)`.
+pub fn ring(config: &SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const COUNT: usize = 6;
+    const MASS: f32 = 5.0;
+
+    let radius = config.world.min_element() * 0.24;
+    let acceleration: f32 = (1..COUNT)
+        .map(|k| {
+            let sin = (std::f32::consts::PI * k as f32 / COUNT as f32).sin();
+            let distance = 2.0 * radius * sin;
+            config.g * MASS * sin / (distance * distance + config.softening.powi(2))
+        })
+        .sum();
+    let speed = (acceleration.max(0.0) * radius).sqrt();
+
+    let centre = config.world / 2.0;
+    (0..COUNT)
+        .map(|id| {
+            let direction = Vec2::from_angle(id as f32 * std::f32::consts::TAU / COUNT as f32);
+            Body {
+                position: centre + direction * radius,
+                velocity: direction.perp() * speed,
+                mass: MASS,
+                radius: config.radius(MASS),
+                ..Body::new_random(id, config, rng)
+            }
+        })
+        .collect()
+}
+
+/// Returns a hierarchical triple, a tight binary with a third body orbiting
+/// it from afar, at random orientations.
+///
+/// Both orbits are circular two-body orbits, the inner one of the two stars
+/// around each other, and the outer one of the third body and the binary as
+/// a whole, which keeps the bodies bound much longer than random ones.
+pub fn hierarchical_triple(config: &SimConfig, rng: &mut Rng) -> Vec<Body> {
+    const STAR_MASS: f32 = 10.0;
+    const THIRD_MASS: f32 = 5.0;
+
+    let separation = config.world.min_element() * 0.06;
+    let distance = config.world.min_element() * 0.3;
+    let inner = Vec2::from_angle(rng.gen_range(0.0, std::f32::consts::TAU));
+    let outer = Vec2::from_angle(rng.gen_range(0.0, std::f32::consts::TAU));
+
+    // Each star orbits the binary's centre of mass, halfway between them.
+    let star_speed = orbital_speed(STAR_MASS, separation / 2.0, separation, config);
+    // The binary and the third body orbit their common centre of mass, which
+    // each is as far from as the other body's share of the total mass.
+    let binary_mass = 2.0 * STAR_MASS;
+    let mass = binary_mass + THIRD_MASS;
+    let binary_speed = orbital_speed(THIRD_MASS, distance * THIRD_MASS / mass, distance, config);
+    let third_speed = orbital_speed(binary_mass, distance * binary_mass / mass, distance, config);
+
+    let centre = config.world / 2.0;
+    let binary = centre - outer * distance * THIRD_MASS / mass;
+    let binary_velocity = -outer.perp() * binary_speed;
+    let states = [
+        (
+            binary + inner * separation / 2.0,
+            binary_velocity + inner.perp() * star_speed,
+            STAR_MASS,
+        ),
+        (
+            binary - inner * separation / 2.0,
+            binary_velocity - inner.perp() * star_speed,
+            STAR_MASS,
+        ),
+        (
+            centre + outer * distance * binary_mass / mass,
+            outer.perp() * third_speed,
+            THIRD_MASS,
+        ),
+    ];
+    states
+        .iter()
+        .enumerate()
+        .map(|(id, &(position, velocity, mass))| Body {
+            position,
+            velocity,
+            mass,
+            radius: config.radius(mass),
+            ..Body::new_random(id, config, rng)
+        })
+        .collect()
+}
+
+/// Returns the restricted three-body problem, a light particle moving around
+/// two heavy primaries held in place, starting from the tip of the
+/// equilateral triangle on the line between them, where the Lagrange point
+/// L4 lies.
+///
+/// The particle starts on a circular orbit around the centre of mass of the
+/// primaries, as if they were one body, plus a launch velocity to hunt for
+/// other orbits with. Gravity is set so that orbit takes a fixed number of
+/// steps, however large the world.
+pub fn restricted(config: &mut SimConfig, rng: &mut Rng, launch: Vec2) -> Vec<Body> {
+    const PRIMARY_MASSES: [f32; 2] = [40.0, 10.0];
+    const PARTICLE_MASS: f32 = 0.1;
+
+    let separation = config.world.min_element() * 0.3;
+    let centre = config.world / 2.0;
+    let primaries = [
+        centre - vec2(separation / 2.0, 0.0),
+        centre + vec2(separation / 2.0, 0.0),
+    ];
+    let mass: f32 = PRIMARY_MASSES.iter().sum();
+    let barycentre = (primaries[0] * PRIMARY_MASSES[0] + primaries[1] * PRIMARY_MASSES[1]) / mass;
+    let position = centre - vec2(0.0, separation * 3.0_f32.sqrt() / 2.0);
+    let offset = position - barycentre;
+    let distance = offset.length();
+
+    let time = PERIOD_STEPS / std::f32::consts::TAU;
+    config.g = distance.powi(3) / (time * time * mass);
+    config.dt = 1.0;
+    let speed = orbital_speed(mass, distance, distance, config);
+
+    let mut bodies: Vec<Body> = primaries
+        .iter()
+        .zip(PRIMARY_MASSES)
+        .enumerate()
+        .map(|(id, (&position, mass))| Body {
+            position,
+            velocity: Vec2::ZERO,
+            mass,
+            radius: config.radius(mass),
+            fixed: true,
+            ..Body::new_random(id, config, rng)
+        })
+        .collect();
+    bodies.push(Body {
+        position,
+        velocity: offset.normalize().perp() * speed + launch,
+        mass: PARTICLE_MASS,
+        radius: config.radius(PARTICLE_MASS),
+        ..Body::new_random(bodies.len(), config, rng)
+    });
+    bodies
+}
+
+/// Returns the speed for a circular orbit of the given radius around a mass
+/// some distance away, or zero if gravity repels.
+fn orbital_speed(mass: f32, radius: f32, distance: f32, config: &SimConfig) -> f32 {
+    let acceleration = config.g * mass / (distance * distance + config.softening.powi(2));
+    (acceleration.max(0.0) * radius).sqrt()
+}
+
+/// Removes the common velocity of the bodies, so their centre of mass stays
+/// where it is.
+fn at_rest(mut bodies: Vec<Body>) -> Vec<Body> {
+    let mass: f32 = bodies.iter().map(|body| body.mass).sum();
+    let momentum = total_momentum(&bodies);
+    for body in &mut bodies {
+        body.velocity -= momentum / mass;
+    }
+    bodies
+}
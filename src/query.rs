@@ -0,0 +1,70 @@
+//! Reading options from the page's query string on the web.
+//!
+//! `index.html` registers a small plugin which hands the query string to the
+//! wasm module, where each `key=value` pair becomes the command line option
+//! `--key value`, so `?seed=1234&bodies=5&elastic&g=5` is the same as
+//! `--seed 1234 --bodies 5 --elastic --g 5`.
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn query_string_length() -> u32;
+    fn query_string(buffer: *mut u8, length: u32);
+}
+
+/// Returns the query string of the page, without the leading `?`.
+#[cfg(target_arch = "wasm32")]
+fn query() -> String {
+    // SAFETY: The plugin writes exactly as many bytes as it reports.
+    let bytes = unsafe {
+        let length = query_string_length();
+        let mut buffer = vec![0; length as usize];
+        query_string(buffer.as_mut_ptr(), length);
+        buffer
+    };
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// There is no page outside the web.
+#[cfg(not(target_arch = "wasm32"))]
+fn query() -> String {
+    String::new()
+}
+
+/// Returns the query string as command line arguments.
+pub fn args() -> Vec<String> {
+    let mut args = Vec::new();
+    for pair in query().split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        args.push(format!("--{}", decode(key)));
+        if !value.is_empty() {
+            args.push(decode(value));
+        }
+    }
+    args
+}
+
+/// Decodes a percent-encoded query string component.
+fn decode(component: &str) -> String {
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut iter = component.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,153 @@
+//! Recording the simulation to screenshots and animated GIFs.
+//!
+//! GIF frames are downscaled and reduced to a fixed 3-3-2 palette as they are
+//! captured, which keeps memory in check and means the encoder does not need
+//! to do any colour quantisation of its own.
+
+use macroquad::prelude::*;
+
+use three_bodies::{lzw, IS_WASM};
+
+/// The file recordings are saved to.
+const RECORDING_PATH: &str = "recording.gif";
+
+/// The time between recorded frames, in hundredths of a second, which is the
+/// unit GIF uses.
+const FRAME_DELAY: u16 = 4;
+
+/// The most frames recorded before the recording stops by itself, which is
+/// 20 seconds at the frame delay above.
+const MAX_FRAMES: usize = 500;
+
+/// The widest a recorded frame can be, larger screens are downscaled.
+const MAX_WIDTH: u32 = 480;
+
+/// Saves a screenshot named after the current time and the seed of the
+/// scenario, or logs that it cannot on wasm, where there is no filesystem.
+pub fn screenshot(seed: u64) {
+    if IS_WASM {
+        info!("Screenshots are not supported on the web");
+        return;
+    }
+    let path = format!("screenshot-{}-{seed}.png", miniquad::date::now() as u64);
+    get_screen_data().export_png(&path);
+    println!("Saved screenshot to {path}");
+}
+
+/// A recording in progress.
+pub struct Recording {
+    /// The palette indices of each frame's pixels, top to bottom.
+    frames: Vec<Vec<u8>>,
+    width: u16,
+    height: u16,
+    /// How often a screen pixel is skipped in either direction.
+    scale: u32,
+    /// The time since the last captured frame, in seconds.
+    elapsed: f32,
+}
+
+impl Recording {
+    /// Starts a new recording at the current screen size.
+    pub fn new() -> Self {
+        let screen_width = screen_width() as u32;
+        let screen_height = screen_height() as u32;
+        let scale = screen_width.div_ceil(MAX_WIDTH).max(1);
+        Self {
+            frames: Vec::new(),
+            width: (screen_width / scale) as u16,
+            height: (screen_height / scale) as u16,
+            scale,
+            elapsed: f32::INFINITY,
+        }
+    }
+
+    /// Captures the screen if enough time has passed since the last frame.
+    ///
+    /// Returns false once the recording is full.
+    pub fn capture(&mut self) -> bool {
+        self.elapsed += get_frame_time();
+        if self.elapsed < f32::from(FRAME_DELAY) / 100.0 {
+            return true;
+        }
+        self.elapsed = 0.0;
+
+        // The screen data is bottom to top, and may not match the recording's
+        // size if the window was resized since it started.
+        let screen = get_screen_data();
+        let (screen_width, screen_height) = (screen.width() as u32, screen.height() as u32);
+        let pixels = screen.get_image_data();
+        let mut frame = Vec::with_capacity(self.width as usize * self.height as usize);
+        for y in 0..u32::from(self.height) {
+            for x in 0..u32::from(self.width) {
+                let (x, y) = (x * self.scale, y * self.scale);
+                if x >= screen_width || y >= screen_height {
+                    frame.push(0);
+                    continue;
+                }
+                let [r, g, b, _] = pixels[((screen_height - y - 1) * screen_width + x) as usize];
+                frame.push((r & 0xe0) | ((g & 0xe0) >> 3) | (b >> 6));
+            }
+        }
+        self.frames.push(frame);
+        self.frames.len() < MAX_FRAMES
+    }
+
+    /// Encodes the recording and saves it to the recording file.
+    pub fn save(self) {
+        if self.frames.is_empty() {
+            return;
+        }
+        match std::fs::write(RECORDING_PATH, self.encode()) {
+            Ok(()) => println!("Saved {} frames to {RECORDING_PATH}", self.frames.len()),
+            Err(err) => eprintln!("Failed to save recording to {RECORDING_PATH}: {err}"),
+        }
+    }
+
+    /// Encodes the recording as a looping GIF.
+    fn encode(&self) -> Vec<u8> {
+        let mut gif = b"GIF89a".to_vec();
+
+        // Logical screen descriptor, with a global colour table of 256
+        // colours.
+        gif.extend(self.width.to_le_bytes());
+        gif.extend(self.height.to_le_bytes());
+        gif.extend([0xf7, 0, 0]);
+        for idx in 0..=255u8 {
+            gif.extend([
+                (idx >> 5) * 255 / 7,
+                ((idx >> 2) & 7) * 255 / 7,
+                (idx & 3) * 255 / 3,
+            ]);
+        }
+
+        // Loop forever.
+        gif.extend([0x21, 0xff, 11]);
+        gif.extend(b"NETSCAPE2.0");
+        gif.extend([3, 1, 0, 0, 0]);
+
+        for frame in &self.frames {
+            // Graphic control extension, with the frame delay.
+            gif.extend([0x21, 0xf9, 4, 0]);
+            gif.extend(FRAME_DELAY.to_le_bytes());
+            gif.extend([0, 0]);
+
+            // Image descriptor, covering the whole screen.
+            gif.push(0x2c);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend(self.width.to_le_bytes());
+            gif.extend(self.height.to_le_bytes());
+            gif.push(0);
+
+            // Image data, in sub-blocks of up to 255 bytes.
+            gif.push(8);
+            for block in lzw::encode(frame).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0);
+        }
+
+        gif.push(0x3b);
+        gif
+    }
+}
@@ -0,0 +1,79 @@
+//! Instant replays of the last moments of the simulation.
+//!
+//! The states of the bodies over the last few hundred steps are kept in a
+//! ring, so that after a collision the approach leading up to it can be
+//! played back in slow motion. Playing a replay does not change the
+//! simulation, which carries on where it was once the replay ends.
+
+use std::collections::{vec_deque, VecDeque};
+
+use three_bodies::{Body, World};
+
+/// How many steps are kept to replay.
+const REPLAY_STEPS: usize = 300;
+
+/// How many steps a replay moves on per frame, slower than the simulation.
+const REPLAY_SPEED: f32 = 0.25;
+
+/// The recent states of the bodies, and how far through them a replay is.
+pub struct Replay {
+    /// The states after each recent step, oldest first.
+    states: VecDeque<Vec<Body>>,
+    /// The step of the simulation of the newest state.
+    steps: u32,
+    /// The state a replay has reached, if one is playing.
+    position: Option<f32>,
+}
+
+impl Replay {
+    /// Creates an empty replay.
+    pub fn new() -> Self {
+        Self {
+            states: VecDeque::with_capacity(REPLAY_STEPS),
+            steps: 0,
+            position: None,
+        }
+    }
+
+    /// Keeps the current state of the world, dropping the oldest one kept
+    /// if there are too many, or all of them if the simulation restarted.
+    pub fn record(&mut self, world: &World) {
+        if world.steps() <= self.steps {
+            self.states.clear();
+        }
+        if self.states.len() == REPLAY_STEPS {
+            self.states.pop_front();
+        }
+        self.states.push_back(world.bodies.clone());
+        self.steps = world.steps();
+    }
+
+    /// Starts replaying the kept states from the oldest one, or stops the
+    /// replay if one is playing.
+    pub fn toggle(&mut self) {
+        self.position = match self.position {
+            None if !self.states.is_empty() => Some(0.0),
+            _ => None,
+        };
+    }
+
+    /// Returns whether a replay is playing.
+    pub fn playing(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Returns the states up to the one the replay has reached, if one is
+    /// playing.
+    pub fn shown(&self) -> Option<vec_deque::Iter<'_, Vec<Body>>> {
+        let position = self.position? as usize;
+        Some(self.states.range(..=position.min(self.states.len() - 1)))
+    }
+
+    /// Moves the replay on by a frame, ending it after the newest state.
+    pub fn advance(&mut self) {
+        self.position = self
+            .position
+            .map(|position| position + REPLAY_SPEED)
+            .filter(|position| (*position as usize) < self.states.len());
+    }
+}
@@ -0,0 +1,45 @@
+//! A small seeded random number generator.
+//!
+//! Scenarios are generated from their own generator rather than macroquad's
+//! global one, so the same seed always gives the same scenario, no matter
+//! what else consumes randomness or how macroquad implements it.
+
+/// A SplitMix64 random number generator.
+#[derive(Clone, Copy)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Returns the seed the generator was last seeded with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the generator from a new seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in the range `low..high`.
+    pub fn gen_range(&mut self, low: f32, high: f32) -> f32 {
+        // The top 24 bits fill an `f32`'s mantissa exactly.
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        low + unit * (high - low)
+    }
+}
@@ -0,0 +1,380 @@
+//! Exporting and loading scenarios as JSON.
+//!
+//! A scenario is an array of bodies, each with a position, velocity, mass,
+//! radius, colour, and whether it is fixed in place:
+//!
+//! ```json
+//! [
+//!   {"position": {"x": 400, "y": 300}, "velocity": {"x": 0.5, "y": -0.25}, "mass": 5, "radius": 6.8, "colour": {"r": 1, "g": 0.5, "b": 0.25, "a": 1}, "fixed": false}
+//! ]
+//! ```
+
+use macroquad::prelude::*;
+
+use crate::{rng::Rng, Body, SimConfig, IS_WASM};
+
+/// The file scenarios are exported to.
+const SCENARIO_PATH: &str = "scenario.json";
+
+/// Exports bodies to the scenario file, or logs them on wasm, where there is
+/// no filesystem.
+pub fn export(bodies: &[Body]) {
+    let json = match to_json(bodies) {
+        Ok(json) => json,
+        Err(err) => {
+            if IS_WASM {
+                info!("Failed to export scenario: {}", err);
+            } else {
+                eprintln!("Failed to export scenario: {err}");
+            }
+            return;
+        }
+    };
+    if IS_WASM {
+        info!("{}", json);
+        return;
+    }
+    match std::fs::write(SCENARIO_PATH, json) {
+        Ok(()) => println!("Exported scenario to {SCENARIO_PATH}"),
+        Err(err) => eprintln!("Failed to export scenario to {SCENARIO_PATH}: {err}"),
+    }
+}
+
+/// Loads bodies from a scenario file.
+pub fn load(path: &str, config: &SimConfig, rng: &mut Rng) -> Result<Vec<Body>, String> {
+    let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    from_json(&json, config, rng)
+}
+
+/// Serialises bodies to JSON.
+///
+/// JSON has no infinities or NaNs, so bodies which have any of those, such as
+/// after a blow-up of the simulation, are refused rather than written out in
+/// a form which cannot be loaded again.
+pub fn to_json(bodies: &[Body]) -> Result<String, String> {
+    if let Some(body) = bodies.iter().find(|body| !is_finite(body)) {
+        return Err(format!("body {} is not finite", body.id));
+    }
+    let bodies: Vec<String> = bodies
+        .iter()
+        .map(|body| {
+            format!(
+                r#"  {{"position": {}, "velocity": {}, "mass": {}, "radius": {}, "colour": {}, "fixed": {}}}"#,
+                vec2_to_json(body.position),
+                vec2_to_json(body.velocity),
+                body.mass,
+                body.radius,
+                colour_to_json(body.colour),
+                body.fixed,
+            )
+        })
+        .collect();
+    Ok(format!("[\n{}\n]\n", bodies.join(",\n")))
+}
+
+/// Returns true if every number of a body is finite.
+fn is_finite(body: &Body) -> bool {
+    let colour = body.colour;
+    body.position.is_finite()
+        && body.velocity.is_finite()
+        && body.mass.is_finite()
+        && body.radius.is_finite()
+        && [colour.r, colour.g, colour.b, colour.a]
+            .iter()
+            .all(|component| component.is_finite())
+}
+
+/// Serialises a vector to a JSON object.
+fn vec2_to_json(vec: Vec2) -> String {
+    format!(r#"{{"x": {}, "y": {}}}"#, vec.x, vec.y)
+}
+
+/// Serialises a colour to a JSON object.
+fn colour_to_json(colour: Color) -> String {
+    format!(
+        r#"{{"r": {}, "g": {}, "b": {}, "a": {}}}"#,
+        colour.r, colour.g, colour.b, colour.a
+    )
+}
+
+/// Deserialises bodies from JSON.
+///
+/// The radius of a body is optional, and derived from its mass if missing.
+/// The colour is optional too, and random if missing, and bodies are not
+/// fixed unless they say so. Unknown keys are rejected, as they are most
+/// likely typos of known ones.
+pub fn from_json(json: &str, config: &SimConfig, rng: &mut Rng) -> Result<Vec<Body>, String> {
+    let Json::Array(bodies) = Parser::parse(json)? else {
+        return Err("expected an array of bodies".to_string());
+    };
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(id, body)| {
+            check_keys(body, BODY_KEYS).map_err(|err| format!("body {id}: {err}"))?;
+            let position = vec2_from_json(body.get("position"))
+                .map_err(|err| format!("body {id}: position: {err}"))?;
+            let velocity = vec2_from_json(body.get("velocity"))
+                .map_err(|err| format!("body {id}: velocity: {err}"))?;
+            let mass = number_from_json(body.get("mass"))
+                .map_err(|err| format!("body {id}: mass: {err}"))?;
+            let radius = match body.get("radius") {
+                Some(radius) => number_from_json(Some(radius))
+                    .map_err(|err| format!("body {id}: radius: {err}"))?,
+                None => config.radius(mass),
+            };
+            let colour = match body.get("colour") {
+                Some(colour) => {
+                    colour_from_json(colour).map_err(|err| format!("body {id}: colour: {err}"))?
+                }
+                None => Body::new_random(id, config, rng).colour,
+            };
+            let fixed = match body.get("fixed") {
+                Some(Json::Boolean(fixed)) => *fixed,
+                Some(_) => return Err(format!("body {id}: fixed: expected a boolean")),
+                None => false,
+            };
+            Ok(Body {
+                id,
+                colour,
+                position,
+                velocity,
+                mass,
+                radius,
+                fixed,
+            })
+        })
+        .collect()
+}
+
+/// The keys of a body.
+const BODY_KEYS: &[&str] = &["position", "velocity", "mass", "radius", "colour", "fixed"];
+
+/// Checks that a JSON object has no keys besides the known ones.
+fn check_keys(json: &Json, known: &[&str]) -> Result<(), String> {
+    match json {
+        Json::Object(entries) => match entries.iter().find(|(key, _)| !known.contains(&&**key)) {
+            Some((key, _)) => Err(format!("unknown key '{key}'")),
+            None => Ok(()),
+        },
+        _ => Err("expected an object".to_string()),
+    }
+}
+
+/// Deserialises a number.
+fn number_from_json(json: Option<&Json>) -> Result<f32, String> {
+    match json {
+        Some(Json::Number(number)) if number.is_finite() => Ok(*number),
+        Some(Json::Number(_)) => Err("out of range".to_string()),
+        Some(_) => Err("expected a number".to_string()),
+        None => Err("missing".to_string()),
+    }
+}
+
+/// Deserialises a vector from a JSON object.
+fn vec2_from_json(json: Option<&Json>) -> Result<Vec2, String> {
+    let json = json.ok_or("missing")?;
+    check_keys(json, &["x", "y"])?;
+    Ok(vec2(
+        number_from_json(json.get("x")).map_err(|err| format!("x: {err}"))?,
+        number_from_json(json.get("y")).map_err(|err| format!("y: {err}"))?,
+    ))
+}
+
+/// Deserialises a colour from a JSON object.
+fn colour_from_json(json: &Json) -> Result<Color, String> {
+    check_keys(json, &["r", "g", "b", "a"])?;
+    let channel = |name| number_from_json(json.get(name)).map_err(|err| format!("{name}: {err}"));
+    Ok(Color::new(
+        channel("r")?,
+        channel("g")?,
+        channel("b")?,
+        channel("a")?,
+    ))
+}
+
+/// A parsed JSON value.
+///
+/// Scenarios only consist of numbers, booleans, arrays, and objects, so the
+/// contents of any other values are not kept.
+enum Json {
+    Other,
+    Boolean(bool),
+    Number(f32),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Returns the value of a key if this is an object containing it.
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// A minimal recursive descent JSON parser.
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// Parses a complete JSON document.
+    fn parse(input: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Returns an error message pointing at the current position.
+    fn error(&self, message: &str) -> String {
+        format!("invalid JSON at byte {}: {message}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Consumes the expected byte, after any whitespace.
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(|_| Json::Other),
+            Some(b't') => self.literal("true").map(|_| Json::Boolean(true)),
+            Some(b'f') => self.literal("false").map(|_| Json::Boolean(false)),
+            Some(b'n') => self.literal("null"),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut entries = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut string = vec![];
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(string).map_err(|_| self.error("invalid UTF-8"));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => b'"',
+                        Some(b'\\') => b'\\',
+                        Some(b'/') => b'/',
+                        Some(b'n') => b'\n',
+                        Some(b't') => b'\t',
+                        Some(b'r') => b'\r',
+                        _ => return Err(self.error("unsupported escape sequence")),
+                    };
+                    string.push(escaped);
+                    self.pos += 1;
+                }
+                Some(byte) => {
+                    string.push(byte);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<Json, String> {
+        if !self.input[self.pos..].starts_with(literal.as_bytes()) {
+            return Err(self.error(&format!("expected {literal}")));
+        }
+        self.pos += literal.len();
+        Ok(Json::Other)
+    }
+}
@@ -0,0 +1,117 @@
+//! Searching seeds for long-lived scenarios.
+//!
+//! Most random scenarios end quickly, with two bodies colliding or one being
+//! flung away. Trying many seeds without drawing anything surfaces the rare
+//! ones which last.
+//!
+//! Each seed can take many steps to try, so a [`Search`] can also be run a
+//! few seeds at a time, such as a few each frame, to keep a window
+//! responsive.
+
+use crate::{has_collision, rng::Rng, step, Body, Ejection, SimConfig};
+
+/// The best seed a search found.
+#[derive(Clone, Copy)]
+pub struct Found {
+    /// The seed of the longest-lived scenario.
+    pub seed: u64,
+    /// How many steps it lasted, up to the most tried.
+    pub steps: u32,
+    /// How many seeds were tried.
+    pub tried: usize,
+}
+
+/// Returns how many steps random bodies from a seed last before any of them
+/// collide or escape, up to a maximum.
+pub fn survival(seed: u64, count: usize, config: &SimConfig, max_steps: u32) -> u32 {
+    let mut bodies = Body::new_random_bodies(count, config, &mut Rng::new(seed));
+    let mut ejection = Ejection::default();
+    for n in 0..max_steps {
+        if !step(&mut bodies, config, config.dt)
+            || bodies.len() < count
+            || has_collision(&bodies, config)
+            || ejection.update(&bodies, config).is_some()
+        {
+            return n;
+        }
+    }
+    max_steps
+}
+
+/// Tries a number of consecutive seeds, and returns the one which lasted the
+/// longest, stopping early if one lasts the maximum.
+pub fn search(first: u64, tries: usize, count: usize, config: &SimConfig, max_steps: u32) -> Found {
+    let mut search = Search::new(first, tries, count, config, max_steps);
+    loop {
+        if let Some(found) = search.advance(tries) {
+            return found;
+        }
+    }
+}
+
+/// A search through consecutive seeds which is still going.
+pub struct Search {
+    /// The first seed tried.
+    first: u64,
+    /// The best seed so far, and how many were tried.
+    found: Found,
+    /// How many seeds to try in total.
+    tries: usize,
+    /// The number of bodies in each scenario.
+    count: usize,
+    /// The config the scenarios are run with, as it was when the search
+    /// started.
+    config: SimConfig,
+    /// The most steps a scenario is run for.
+    max_steps: u32,
+    /// Whether a scenario lasted the most steps, which ends the search early.
+    done: bool,
+}
+
+impl Search {
+    /// Starts a search through a number of seeds from the first one.
+    pub fn new(first: u64, tries: usize, count: usize, config: &SimConfig, max_steps: u32) -> Self {
+        Self {
+            first,
+            found: Found {
+                seed: first,
+                steps: 0,
+                tried: 0,
+            },
+            tries,
+            count,
+            config: *config,
+            max_steps,
+            done: false,
+        }
+    }
+
+    /// Tries up to a number of further seeds, and returns the best one once
+    /// the search is over.
+    pub fn advance(&mut self, seeds: usize) -> Option<Found> {
+        for _ in 0..seeds {
+            if self.done || self.found.tried >= self.tries {
+                break;
+            }
+            let seed = self.first.wrapping_add(self.found.tried as u64);
+            let steps = survival(seed, self.count, &self.config, self.max_steps);
+            self.found.tried += 1;
+            if steps > self.found.steps || self.found.tried == 1 {
+                self.found.seed = seed;
+                self.found.steps = steps;
+            }
+            self.done = steps >= self.max_steps;
+        }
+        (self.done || self.found.tried >= self.tries).then_some(self.found)
+    }
+
+    /// Returns the best seed so far, and how many were tried.
+    pub fn progress(&self) -> Found {
+        self.found
+    }
+
+    /// Returns how many seeds the search tries at most.
+    pub fn tries(&self) -> usize {
+        self.tries
+    }
+}
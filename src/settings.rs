@@ -0,0 +1,220 @@
+//! Loading default settings from a TOML file.
+//!
+//! Only the subset of TOML needed for flat settings is supported, one
+//! `key = value` per line, where values are numbers, booleans, or strings:
+//!
+//! ```toml
+//! # A calmer default setup.
+//! bodies = 4
+//! seed = 42
+//! g = 9.81
+//! dt = 0.5
+//! softening = 2
+//! damping = 0.001
+//! integrator = "rk4"
+//! boundary = "bounce"
+//! collision_mode = "elastic"
+//! trail_fade = 0.99
+//! min_mass = 1
+//! max_mass = 10
+//! max_velocity = 1
+//! spawn_margin = 0.25
+//! palette = "pastel"
+//! background = "#101020"
+//! ```
+//!
+//! Any keys left out keep their defaults. Setting `pixels_per_au` or
+//! `steps_per_year` shows the simulation in physical units, which sets `g`
+//! unless it comes later, and `max_velocity` in AU per year. Other keys stay
+//! in pixels and steps.
+
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::Color;
+
+use crate::{units::Units, SimConfig, IS_WASM};
+
+/// The name of the settings file, which is looked for in the working
+/// directory and next to the binary.
+pub const SETTINGS_FILE: &str = "three-bodies.toml";
+
+/// Default settings, some of which can also be set on the command line.
+#[derive(Clone, Copy, Default)]
+pub struct Settings {
+    /// The number of bodies to simulate.
+    pub bodies: Option<usize>,
+    /// The seed for the first scenario.
+    pub seed: Option<u64>,
+    /// The background colour.
+    pub background: Option<Color>,
+    /// The simulation config.
+    pub config: SimConfig,
+}
+
+/// Returns the path of the settings file, if there is one.
+pub fn path() -> Option<PathBuf> {
+    if IS_WASM {
+        return None;
+    }
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(SETTINGS_FILE)));
+    [Some(PathBuf::from(SETTINGS_FILE)), exe_dir]
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+}
+
+/// Loads settings from a settings file.
+pub fn load(path: &Path) -> Result<Settings, String> {
+    let toml = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parse(&toml)
+}
+
+/// Parses settings from TOML.
+pub fn parse(toml: &str) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+    for (idx, line) in toml.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected 'key = value'", idx + 1));
+        };
+        Value::parse(value.trim())
+            .and_then(|value| apply(&mut settings, key.trim(), &value))
+            .map_err(|err| format!("line {}: {err}", idx + 1))?;
+    }
+    Ok(settings)
+}
+
+/// Sets a single setting.
+fn apply(settings: &mut Settings, key: &str, value: &Value) -> Result<(), String> {
+    let config = &mut settings.config;
+    match key {
+        "bodies" => settings.bodies = Some(value.integer()? as usize),
+        "seed" => settings.seed = Some(value.integer()?),
+        "background" => settings.background = Some(parse_colour(value.string()?)?),
+        "g" => config.g = value.number()? as f32,
+        "dt" => config.dt = value.number()? as f32,
+        "softening" => config.softening = value.number()? as f32,
+        "restitution" => config.restitution = value.number()? as f32,
+        "damping" => config.damping = value.number()? as f32,
+        "trail_fade" => {
+            let fade = value.number()?;
+            if !(fade > 0.0 && fade <= 1.0) {
+                return Err(format!(
+                    "trail_fade must be above 0 and at most 1, not {fade}"
+                ));
+            }
+            config.trail_fade = fade as f32;
+        }
+        "min_mass" => config.min_mass = value.number()? as f32,
+        "max_mass" => config.max_mass = value.number()? as f32,
+        "max_velocity" => config.max_velocity = value.number()? as f32,
+        "spawn_margin" => config.spawn_margin = value.number()? as f32,
+        "pixels_per_au" => config.set_units(Units {
+            pixels_per_au: value.number()? as f32,
+            ..config.units.unwrap_or_default()
+        }),
+        "steps_per_year" => config.set_units(Units {
+            steps_per_year: value.number()? as f32,
+            ..config.units.unwrap_or_default()
+        }),
+        "barnes_hut" => config.barnes_hut = value.boolean()?,
+        "integrator" => config.integrator = value.string()?.parse()?,
+        "boundary" => config.set_boundary(value.string()?.parse()?),
+        "collision_mode" => config.collision_mode = value.string()?.parse()?,
+        "palette" => config.palette = value.string()?.parse()?,
+        _ => return Err(format!("unknown key '{key}'")),
+    }
+    Ok(())
+}
+
+/// Parses a colour written as hex, like `#ff8000`.
+pub fn parse_colour(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    match u32::from_str_radix(digits, 16) {
+        Ok(rgb) if digits.len() == 6 => Ok(Color::from_rgba(
+            (rgb >> 16) as u8,
+            (rgb >> 8) as u8,
+            rgb as u8,
+            255,
+        )),
+        _ => Err(format!("invalid colour '{hex}', expected #rrggbb")),
+    }
+}
+
+/// Removes a trailing comment from a line, unless it is within a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, char) in line.char_indices() {
+        match char {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// A parsed TOML value.
+enum Value<'a> {
+    /// A whole number, kept apart so that large ones like seeds stay exact.
+    Integer(u64),
+    Number(f64),
+    Boolean(bool),
+    String(&'a str),
+}
+
+impl<'a> Value<'a> {
+    fn parse(value: &'a str) -> Result<Self, String> {
+        match value {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => {
+                if let Some(string) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    return Ok(Value::String(string));
+                }
+                let number = value.replace('_', "");
+                if let Ok(integer) = number.parse() {
+                    return Ok(Value::Integer(integer));
+                }
+                number
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| format!("invalid value '{value}'"))
+            }
+        }
+    }
+
+    fn number(&self) -> Result<f64, String> {
+        match self {
+            Value::Integer(integer) => Ok(*integer as f64),
+            Value::Number(number) => Ok(*number),
+            _ => Err("expected a number".to_string()),
+        }
+    }
+
+    fn integer(&self) -> Result<u64, String> {
+        match self {
+            Value::Integer(integer) => Ok(*integer),
+            _ => Err("expected a whole number".to_string()),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, String> {
+        match self {
+            Value::Boolean(boolean) => Ok(*boolean),
+            _ => Err("expected a boolean".to_string()),
+        }
+    }
+
+    fn string(&self) -> Result<&'a str, String> {
+        match self {
+            Value::String(string) => Ok(string),
+            _ => Err("expected a string".to_string()),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Collision sounds.
+//!
+//! Sound needs the `sound` feature, which pulls in macroquad's audio module
+//! and with it ALSA on Linux. It is off until turned on with a key, which on
+//! the web is also the user gesture browsers require before playing audio.
+//!
+//! The collision sound is synthesised at startup rather than loaded from a
+//! file, a short thud whose volume follows how hard the bodies hit.
+
+#[cfg(feature = "sound")]
+use macroquad::audio::{self, PlaySoundParams};
+
+/// The sample rate of the collision sound.
+#[cfg(feature = "sound")]
+const SAMPLE_RATE: u32 = 22_050;
+
+/// The relative speed at which collisions are played at half volume.
+#[cfg(feature = "sound")]
+const IMPACT_SCALE: f32 = 2.0;
+
+/// Collision sounds, which can be turned on and off.
+pub struct Sounds {
+    #[cfg(feature = "sound")]
+    collision: audio::Sound,
+    enabled: bool,
+}
+
+impl Sounds {
+    /// Prepares the collision sound, turned off.
+    #[cfg(feature = "sound")]
+    pub async fn load() -> Self {
+        let collision = audio::load_sound_from_bytes(&thud())
+            .await
+            .expect("the collision sound is a valid WAV file");
+        Self {
+            collision,
+            enabled: false,
+        }
+    }
+
+    /// There are no sounds without the `sound` feature.
+    #[cfg(not(feature = "sound"))]
+    pub async fn load() -> Self {
+        Self { enabled: false }
+    }
+
+    /// Returns whether sound is available in this build.
+    pub fn available(&self) -> bool {
+        cfg!(feature = "sound")
+    }
+
+    /// Returns whether sounds are played.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns sounds on or off, if they are available.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled && self.available();
+    }
+
+    /// Plays the collision sound, louder for harder impacts, if sounds are
+    /// on.
+    #[cfg(feature = "sound")]
+    pub fn collide(&self, impact: f32) {
+        if self.enabled {
+            audio::play_sound(
+                &self.collision,
+                PlaySoundParams {
+                    looped: false,
+                    volume: impact / (impact + IMPACT_SCALE),
+                },
+            );
+        }
+    }
+
+    /// There are no sounds to play without the `sound` feature.
+    #[cfg(not(feature = "sound"))]
+    pub fn collide(&self, _impact: f32) {}
+}
+
+/// Returns a WAV file of a short, low thud, a sine wave dropping in pitch
+/// from 180 Hz to 60 Hz as it decays.
+#[cfg(feature = "sound")]
+fn thud() -> Vec<u8> {
+    const DURATION: f32 = 0.2;
+    let samples: Vec<i16> = (0..(SAMPLE_RATE as f32 * DURATION) as u32)
+        .map(|idx| {
+            let time = idx as f32 / SAMPLE_RATE as f32;
+            // The phase is the integral of the falling frequency.
+            let phase = std::f32::consts::TAU * (60.0 * time + 6.0 * (1.0 - (-time * 20.0).exp()));
+            let envelope = (-time * 25.0).exp();
+            (phase.sin() * envelope * i16::MAX as f32) as i16
+        })
+        .collect();
+
+    let data_length = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_length as usize);
+    wav.extend(b"RIFF");
+    wav.extend((36 + data_length).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16u32.to_le_bytes());
+    // Uncompressed PCM, one channel.
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(SAMPLE_RATE.to_le_bytes());
+    // The byte rate and the bytes per sample, of 16 bits each.
+    wav.extend((SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend(2u16.to_le_bytes());
+    wav.extend(16u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend(data_length.to_le_bytes());
+    for sample in samples {
+        wav.extend(sample.to_le_bytes());
+    }
+    wav
+}
@@ -0,0 +1,57 @@
+//! Recording the trajectories of bodies to CSV.
+//!
+//! Each step adds one row per body, with the columns `frame`, `id`, `x`, `y`,
+//! `vx`, `vy`, and `mass`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::Body;
+
+/// How many frames are buffered before they are flushed to the file, so a
+/// crash loses at most this many.
+const FLUSH_INTERVAL: u64 = 60;
+
+/// A trajectory file being written to.
+pub struct Trajectory {
+    file: BufWriter<File>,
+    frame: u64,
+}
+
+impl Trajectory {
+    /// Creates the trajectory file and writes the header.
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "frame,id,x,y,vx,vy,mass")?;
+        Ok(Self { file, frame: 0 })
+    }
+
+    /// Writes the current state of the bodies as the next frame.
+    pub fn record(&mut self, bodies: &[Body]) -> std::io::Result<()> {
+        for body in bodies {
+            writeln!(
+                self.file,
+                "{},{},{},{},{},{},{}",
+                self.frame,
+                body.id,
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+                body.mass,
+            )?;
+        }
+        self.frame += 1;
+        if self.frame.is_multiple_of(FLUSH_INTERVAL) {
+            self.file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes any buffered rows to the file.
+    pub fn close(mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,64 @@
+//! Showing the simulation in physical units.
+//!
+//! By default, lengths are in pixels, time is in steps, and masses and the
+//! gravitational constant are arbitrary. With units, lengths are shown in
+//! astronomical units (AU), time in years, and masses in solar masses, and
+//! the gravitational constant is scaled so that orbits match: `4 pi^2` in
+//! these units, as by Kepler's third law the Earth takes a year to orbit one
+//! solar mass at 1 AU.
+//!
+//! Units are only a scale for display. The simulation still runs in pixels
+//! and steps, and only gravity and the largest initial velocity of random
+//! bodies are converted. Every other setting, such as the time step,
+//! softening, damping, and the sizes of bodies, stays in pixels and steps.
+
+use std::f32::consts::TAU;
+
+/// How physical units map to pixels and steps.
+#[derive(Clone, Copy)]
+pub struct Units {
+    /// How many pixels long an AU is drawn.
+    pub pixels_per_au: f32,
+    /// How many steps of the simulation a year takes.
+    pub steps_per_year: f32,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            pixels_per_au: 100.0,
+            steps_per_year: 600.0,
+        }
+    }
+}
+
+impl Units {
+    /// The gravitational constant in AU^3 / (solar mass * year^2).
+    pub const G: f32 = TAU * TAU;
+
+    /// Returns the gravitational constant in pixels^3 / (solar mass *
+    /// step^2).
+    pub fn g(&self) -> f32 {
+        Self::G * self.pixels_per_au.powi(3) / self.steps_per_year.powi(2)
+    }
+
+    /// Converts a length in pixels to AU.
+    pub fn length(&self, pixels: f32) -> f32 {
+        pixels / self.pixels_per_au
+    }
+
+    /// Converts a speed in pixels per step to AU per year.
+    pub fn speed(&self, pixels_per_step: f32) -> f32 {
+        pixels_per_step * self.steps_per_year / self.pixels_per_au
+    }
+
+    /// Converts a speed in AU per year to pixels per step.
+    pub fn speed_to_pixels(&self, au_per_year: f32) -> f32 {
+        au_per_year * self.pixels_per_au / self.steps_per_year
+    }
+
+    /// Converts a number of steps to years.
+    pub fn time(&self, steps: f32) -> f32 {
+        steps / self.steps_per_year
+    }
+}
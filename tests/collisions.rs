@@ -0,0 +1,213 @@
+use macroquad::prelude::*;
+use three_bodies::{
+    kinetic_energy, resolve_collisions, steps_until_collision, total_momentum, Body, Boundary,
+    CollisionMode, SimConfig, World,
+};
+
+/// Returns a body of unit mass and radius 5.
+fn ball(id: usize, position: Vec2, velocity: Vec2) -> Body {
+    Body {
+        id,
+        colour: WHITE,
+        position,
+        velocity,
+        mass: 1.0,
+        radius: 5.0,
+        fixed: false,
+    }
+}
+
+/// Returns a world with two bodies without gravity heading straight at each
+/// other, which touch after 25 steps.
+fn head_on(collision_mode: CollisionMode) -> World {
+    let mut config = SimConfig {
+        collision_mode,
+        g: 0.0,
+        ..SimConfig::default()
+    };
+    config.set_boundary(Boundary::Open);
+    World::new(
+        vec![
+            ball(0, vec2(370.0, 300.0), vec2(1.0, 0.0)),
+            ball(1, vec2(430.0, 300.0), vec2(-1.0, 0.0)),
+        ],
+        config,
+    )
+}
+
+/// Returns an elastic config without gravity or boundaries.
+fn elastic() -> SimConfig {
+    let mut config = SimConfig {
+        collision_mode: CollisionMode::Elastic,
+        restitution: 1.0,
+        g: 0.0,
+        ..SimConfig::default()
+    };
+    config.set_boundary(Boundary::Open);
+    config
+}
+
+#[test]
+fn head_on_equal_masses_swap_velocities() {
+    let mut bodies = [
+        ball(0, vec2(395.0, 300.0), vec2(1.0, 0.0)),
+        ball(1, vec2(405.0, 300.0), vec2(-3.0, 0.0)),
+    ];
+    assert_eq!(resolve_collisions(&mut bodies, &elastic()), [true, true]);
+    assert!((bodies[0].velocity - vec2(-3.0, 0.0)).length() < 1e-5);
+    assert!((bodies[1].velocity - vec2(1.0, 0.0)).length() < 1e-5);
+}
+
+#[test]
+fn glancing_bounce_conserves_energy_and_tangential_velocity() {
+    // The bodies overlap along a diagonal, while moving mostly along the x
+    // axis.
+    let normal = vec2(1.0, 1.0).normalize();
+    let tangent = normal.perp();
+    let mut bodies = [
+        ball(0, vec2(400.0, 300.0), vec2(2.0, 0.5)),
+        ball(1, vec2(400.0, 300.0) + normal * 9.5, vec2(-1.0, 0.0)),
+    ];
+    bodies[1].mass = 3.0;
+    let before = bodies;
+    resolve_collisions(&mut bodies, &elastic());
+    assert!((kinetic_energy(&bodies) - kinetic_energy(&before)).abs() < 1e-5);
+    for (body, before) in bodies.iter().zip(&before) {
+        assert!((body.velocity.dot(tangent) - before.velocity.dot(tangent)).abs() < 1e-5);
+        assert!((body.velocity - before.velocity).length() > 0.1);
+    }
+}
+
+#[test]
+fn overlapping_bodies_are_pushed_apart() {
+    let mut wrapping = elastic();
+    wrapping.set_boundary(Boundary::Wrap);
+    let cases = [
+        (elastic(), vec2(398.0, 300.0), vec2(404.0, 300.0)),
+        // Overlapping across the left and right edges of the world.
+        (wrapping, vec2(2.0, 300.0), vec2(796.0, 301.0)),
+    ];
+    for (config, a, b) in cases {
+        let mut bodies = [ball(0, a, Vec2::ZERO), ball(1, b, Vec2::ZERO)];
+        bodies[1].mass = 2.0;
+        resolve_collisions(&mut bodies, &config);
+        let distance = bodies[0].delta_to(&bodies[1], &config).length();
+        assert!(distance >= 10.0 - 1e-4, "{distance}");
+        for body in &bodies {
+            assert!(body.position.x >= 0.0 && body.position.x < config.world.x);
+        }
+    }
+}
+
+#[test]
+fn stop_stops_on_collision() {
+    let mut world = head_on(CollisionMode::Stop);
+    for _ in 0..40 {
+        world.step(1.0);
+    }
+    assert!(world.collided());
+    assert_eq!(world.collisions(), 1);
+    assert!(world.steps() < 40);
+    // The collision flashes halfway between the bodies.
+    assert_eq!(world.effects.len(), 1);
+    assert!((world.effects[0].position - vec2(400.0, 300.0)).length() < 1e-3);
+}
+
+#[test]
+fn elastic_bounces_bodies_apart() {
+    let mut world = head_on(CollisionMode::Elastic);
+    for _ in 0..40 {
+        world.step(1.0);
+    }
+    assert!(!world.collided());
+    assert!(world.collisions() >= 1);
+    assert_eq!(world.bodies.len(), 2);
+    assert!(world.bodies[0].velocity.x < 0.0 && world.bodies[1].velocity.x > 0.0);
+    assert!(world.bodies[0].position.x < world.bodies[1].position.x);
+    // No kinetic energy is lost with full restitution.
+    assert!(world.dissipated().abs() < 1e-5);
+}
+
+#[test]
+fn merge_conserves_mass_and_momentum() {
+    let mut world = head_on(CollisionMode::Merge);
+    let momentum = total_momentum(&world.bodies);
+    for _ in 0..40 {
+        world.step(1.0);
+    }
+    assert_eq!(world.collisions(), 1);
+    assert_eq!(world.bodies.len(), 1);
+    assert_eq!(world.bodies[0].mass, 2.0);
+    assert!((total_momentum(&world.bodies) - momentum).length() < 1e-5);
+    // All kinetic energy is lost, as the merged body is at rest.
+    assert!((world.dissipated() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn pass_through_lets_bodies_overlap() {
+    let mut world = head_on(CollisionMode::PassThrough);
+    for _ in 0..80 {
+        world.step(1.0);
+    }
+    assert!(!world.collided());
+    assert_eq!(world.collisions(), 1);
+    assert_eq!(world.bodies.len(), 2);
+    assert!(world.bodies[0].position.x > world.bodies[1].position.x);
+}
+
+#[test]
+fn elastic_bounces_off_fixed_bodies() {
+    let mut world = head_on(CollisionMode::Elastic);
+    world.bodies[1].toggle_fixed();
+    for _ in 0..80 {
+        world.step(1.0);
+    }
+    // The fixed body stays put, and the other one bounces straight back.
+    assert_eq!(world.bodies[1].position, vec2(430.0, 300.0));
+    assert_eq!(world.bodies[1].velocity, Vec2::ZERO);
+    assert!((world.bodies[0].velocity.x + 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn elastic_conserves_momentum_and_energy_between_three_bodies() {
+    let mut config = SimConfig {
+        collision_mode: CollisionMode::Elastic,
+        g: 0.0,
+        ..SimConfig::default()
+    };
+    config.set_boundary(Boundary::Open);
+    // Three bodies of different masses all meeting in the same point.
+    let bodies = (0..3)
+        .map(|id| {
+            let direction = Vec2::from_angle(id as f32 * std::f32::consts::TAU / 3.0);
+            Body {
+                mass: id as f32 + 1.0,
+                ..ball(id, vec2(400.0, 300.0) + direction * 30.0, -direction)
+            }
+        })
+        .collect();
+    let mut world = World::new(bodies, config);
+    let momentum = total_momentum(&world.bodies);
+    let kinetic = kinetic_energy(&world.bodies);
+    for _ in 0..40 {
+        world.step(1.0);
+    }
+    assert!(world.collisions() >= 1);
+    assert!((total_momentum(&world.bodies) - momentum).length() < 1e-4);
+    assert!((kinetic_energy(&world.bodies) - kinetic).abs() < 1e-4);
+}
+
+#[test]
+fn steps_until_collision_looks_ahead() {
+    let world = head_on(CollisionMode::Elastic);
+    // The bodies are 50 apart between their edges, closing in at 2 a step.
+    assert_eq!(
+        steps_until_collision(&world.bodies, &world.config, 100),
+        Some(25)
+    );
+    assert_eq!(
+        steps_until_collision(&world.bodies, &world.config, 10),
+        None
+    );
+    assert_eq!(world.bodies[0].position, vec2(370.0, 300.0));
+}
@@ -0,0 +1,57 @@
+use macroquad::prelude::*;
+use three_bodies::{rng::Rng, search, Body, SimConfig, World};
+
+/// The seed of the golden run.
+const SEED: u64 = 11;
+
+/// How many steps the golden run takes.
+const STEPS: u32 = 1000;
+
+/// The positions of the bodies at the end of the golden run, in a world of
+/// the default size.
+const GOLDEN_POSITIONS: [Vec2; 3] = [
+    Vec2::new(49.379173, 525.9525),
+    Vec2::new(181.51122, 406.25684),
+    Vec2::new(224.00893, 389.8574),
+];
+
+/// How far the positions may be off, to allow for floating point differences
+/// between platforms, but not for changes in the physics.
+const TOLERANCE: f32 = 0.01;
+
+#[test]
+fn fixed_seed_matches_golden_positions() {
+    let config = SimConfig::default();
+    let mut rng = Rng::new(SEED);
+    let mut world = World::new(Body::new_random_bodies(3, &config, &mut rng), config);
+    for _ in 0..STEPS {
+        world.step(config.dt);
+    }
+    assert!(!world.collided());
+    assert_eq!(world.steps(), STEPS);
+    for (body, golden) in world.bodies.iter().zip(GOLDEN_POSITIONS) {
+        assert!(
+            body.position.distance(golden) <= TOLERANCE,
+            "body {} at {}, expected {golden}",
+            body.id,
+            body.position
+        );
+    }
+}
+
+#[test]
+fn searching_a_few_seeds_at_a_time_finds_the_same_seed() {
+    let config = SimConfig::default();
+    let whole = search::search(100, 12, 3, &config, 500);
+    let mut search = search::Search::new(100, 12, 3, &config, 500);
+    let found = loop {
+        if let Some(found) = search.advance(5) {
+            break found;
+        }
+        assert!(search.progress().tried.is_multiple_of(5));
+    };
+    assert_eq!(
+        (found.seed, found.steps, found.tried),
+        (whole.seed, whole.steps, whole.tried)
+    );
+}
@@ -0,0 +1,137 @@
+use three_bodies::{lzw, rng::Rng};
+
+const CLEAR: usize = 256;
+const END: usize = 257;
+
+/// What decoding a stream of codes found besides the data.
+#[derive(Default)]
+struct Decoded {
+    indices: Vec<u8>,
+    /// How many codes were in the table when the end code was read.
+    codes_at_end: usize,
+    /// How many times the table was cleared after the start.
+    clears: usize,
+    /// How many bits were read, up to and including the end code.
+    bits: usize,
+}
+
+/// Decompresses GIF LZW data with a minimum code size of 8 bits, the way GIF
+/// decoders do, panicking on any invalid code.
+fn decode(bytes: &[u8]) -> Decoded {
+    let mut decoded = Decoded::default();
+    let mut table: Vec<Vec<u8>> = vec![];
+    let mut previous: Option<Vec<u8>> = None;
+    let mut code_size = 9;
+    let mut bit = 0;
+    let mut first = true;
+    loop {
+        let code = (0..code_size)
+            .map(|idx| {
+                let bit = bit + idx;
+                let byte = bytes.get(bit / 8).expect("data ends before the end code");
+                usize::from((byte >> (bit % 8)) & 1) << idx
+            })
+            .sum::<usize>();
+        bit += code_size;
+
+        if code == CLEAR {
+            table = (0..=255).map(|idx| vec![idx]).collect();
+            // The clear and end codes have no entries of their own.
+            table.extend([vec![], vec![]]);
+            previous = None;
+            code_size = 9;
+            decoded.clears += usize::from(!first);
+            first = false;
+            continue;
+        }
+        assert!(!first, "data does not start with a clear code");
+        if code == END {
+            decoded.codes_at_end = table.len();
+            decoded.bits = bit;
+            assert!(bit.div_ceil(8) == bytes.len(), "data after the end code");
+            return decoded;
+        }
+
+        let entry = match (&previous, table.get(code)) {
+            (_, Some(entry)) => entry.clone(),
+            // A code which is being defined by this very step.
+            (Some(previous), None) if code == table.len() => {
+                let mut entry = previous.clone();
+                entry.push(previous[0]);
+                entry
+            }
+            _ => panic!("invalid code {code} with {} codes", table.len()),
+        };
+        decoded.indices.extend(&entry);
+        if let Some(mut previous) = previous.take() {
+            if table.len() < 4096 {
+                previous.push(entry[0]);
+                table.push(previous);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+        }
+        previous = Some(entry);
+    }
+}
+
+/// Checks that some data survives a round trip, and returns what decoding
+/// found.
+fn round_trip(data: &[u8]) -> Decoded {
+    let decoded = decode(&lzw::encode(data));
+    assert!(decoded.indices == data, "mismatch at length {}", data.len());
+    decoded
+}
+
+/// Returns random indices from a seed.
+fn random(seed: u64, len: usize) -> Vec<u8> {
+    let mut rng = Rng::new(seed);
+    (0..len).map(|_| rng.next_u64() as u8).collect()
+}
+
+#[test]
+fn random_data_survives_a_round_trip_at_every_code_size() {
+    let data = random(36, 40_000);
+    // How many times the table was cleared and how full it is only ever grow
+    // with the length, and the table by at most one code per index, so the
+    // length ending right at some size after some clears can be bisected.
+    let mut crossed = false;
+    let mut start = 0;
+    for clears in 0.. {
+        for size in [512, 1024, 2048, 4096] {
+            let (mut low, mut high) = (start, data.len());
+            while low < high {
+                let mid = (low + high) / 2;
+                let decoded = round_trip(&data[..mid]);
+                if (decoded.clears, decoded.codes_at_end) < (clears, size) {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            let decoded = round_trip(&data[..low]);
+            assert_eq!((decoded.clears, decoded.codes_at_end), (clears, size));
+            // Where the last code fills up the current code size, the end
+            // code is one bit wider, which only shows in the data where that
+            // bit starts a new byte.
+            crossed |= size < 4096 && decoded.bits % 8 == 1;
+            start = low;
+        }
+        if crossed {
+            break;
+        }
+    }
+}
+
+#[test]
+fn repetitive_data_survives_a_round_trip() {
+    // Long runs make the encoder use codes right as it defines them.
+    let runs: Vec<u8> = (0..3000u32).map(|idx| (idx / 700) as u8).collect();
+    let pattern: Vec<u8> = (0..3000u32).map(|idx| (idx % 7) as u8).collect();
+    for end in (0..3000).step_by(97) {
+        round_trip(&runs[..end]);
+        round_trip(&pattern[..end]);
+    }
+    assert!(round_trip(&[]).indices.is_empty());
+}
@@ -0,0 +1,212 @@
+use macroquad::prelude::*;
+use three_bodies::{
+    accelerations, angular_momentum, ejected_body, has_collision, potential_at, presets, rng::Rng,
+    step, total_energy, Body, Boundary, CollisionMode, Ejection, Integrator, SimConfig,
+    WRAP_EJECTION_STEPS,
+};
+
+/// Returns a body of radius 5.
+fn body(id: usize, position: Vec2, velocity: Vec2, mass: f32) -> Body {
+    Body {
+        id,
+        colour: WHITE,
+        position,
+        velocity,
+        mass,
+        radius: 5.0,
+        fixed: false,
+    }
+}
+
+#[test]
+fn symmetric_accelerations_match_per_body_accelerations() {
+    let mut rng = Rng::new(3);
+    let config = SimConfig {
+        damping: 0.01,
+        ..SimConfig::default()
+    };
+    let mut bodies = Body::new_random_bodies(3, &config, &mut rng);
+    bodies[1].toggle_fixed();
+    for (body, acceleration) in bodies.iter().zip(accelerations(&bodies, &config)) {
+        let expected = body.acceleration(&bodies, &config);
+        assert!(
+            (acceleration - expected).length() <= 1e-5 * expected.length().max(1.0),
+            "{acceleration} != {expected}"
+        );
+    }
+}
+
+#[test]
+fn barnes_hut_approximates_exact_accelerations() {
+    let mut rng = Rng::new(61);
+    let config = SimConfig {
+        barnes_hut: true,
+        ..SimConfig::default()
+    };
+    let bodies = Body::new_random_bodies(300, &config, &mut rng);
+    let approximate = accelerations(&bodies, &config);
+    let exact = accelerations(
+        &bodies,
+        &SimConfig {
+            barnes_hut: false,
+            ..config
+        },
+    );
+    let error: f32 = approximate
+        .iter()
+        .zip(&exact)
+        .map(|(approximate, exact)| (*approximate - *exact).length_squared())
+        .sum();
+    let magnitude: f32 = exact.iter().map(|exact| exact.length_squared()).sum();
+    assert!(
+        (error / magnitude).sqrt() < 0.02,
+        "relative error {}",
+        (error / magnitude).sqrt()
+    );
+}
+
+#[test]
+fn coincident_bodies_stay_finite() {
+    for integrator in [Integrator::Euler, Integrator::Verlet, Integrator::RK4] {
+        let config = SimConfig {
+            integrator,
+            softening: 0.0,
+            collision_mode: CollisionMode::PassThrough,
+            ..SimConfig::default()
+        };
+        let centre = vec2(400.0, 300.0);
+        let mut bodies = vec![
+            body(0, centre, Vec2::ZERO, 5.0),
+            body(1, centre, Vec2::ZERO, 5.0),
+        ];
+        for _ in 0..10 {
+            step(&mut bodies, &config, config.dt);
+        }
+        for body in &bodies {
+            assert!(body.velocity.x.is_finite() && body.velocity.y.is_finite());
+            assert!(body.position.x.is_finite() && body.position.y.is_finite());
+        }
+    }
+}
+
+#[test]
+fn bodies_escape_around_a_wrapping_world() {
+    let config = SimConfig::default();
+    assert!(config.boundary == Boundary::Wrap);
+    let heavy = body(0, vec2(100.0, 300.0), Vec2::ZERO, 50.0);
+    // A light body 300 pixels from a heavy one, but only 200 around the
+    // edge of the world.
+    let escaping = [heavy, body(1, vec2(700.0, 300.0), vec2(-5.0, 0.0), 1.0)];
+    assert_eq!(ejected_body(&escaping, &config), Some(1));
+    let bound = [heavy, body(1, vec2(700.0, 300.0), vec2(-1.0, 0.0), 1.0)];
+    assert_eq!(ejected_body(&bound, &config), None);
+    let near = [
+        body(0, vec2(300.0, 300.0), Vec2::ZERO, 50.0),
+        body(1, vec2(400.0, 300.0), vec2(-5.0, 0.0), 1.0),
+    ];
+    assert_eq!(ejected_body(&near, &config), None);
+}
+
+#[test]
+fn moons_of_a_wide_pair_do_not_escape_a_wrapping_world() {
+    let config = SimConfig::default();
+    let speed = (config.g * 50.0 / 20.0).sqrt();
+    // The moon orbits the first of the pair, but is further than a quarter of
+    // the world from the centre of mass of the pair, and faster than its
+    // escape velocity if all of its mass was there.
+    let bodies = [
+        body(0, vec2(240.0, 300.0), Vec2::ZERO, 50.0),
+        body(1, vec2(560.0, 300.0), Vec2::ZERO, 50.0),
+        body(2, vec2(240.0, 280.0), vec2(speed, 0.0), 1.0),
+    ];
+    assert_eq!(ejected_body(&bodies, &config), None);
+}
+
+#[test]
+fn bodies_only_escape_a_wrapping_world_if_they_stay_unbound() {
+    let config = SimConfig::default();
+    let heavy = body(0, vec2(100.0, 300.0), Vec2::ZERO, 50.0);
+    let escaping = [heavy, body(1, vec2(700.0, 300.0), vec2(-5.0, 0.0), 1.0)];
+    let bound = [heavy, body(1, vec2(700.0, 300.0), vec2(-1.0, 0.0), 1.0)];
+    let mut ejection = Ejection::default();
+    // A slingshot leaves the light body unbound for a while, but not long
+    // enough.
+    for _ in 0..WRAP_EJECTION_STEPS {
+        assert_eq!(ejection.update(&escaping, &config), None);
+    }
+    assert_eq!(ejection.update(&bound, &config), None);
+    for _ in 0..WRAP_EJECTION_STEPS {
+        assert_eq!(ejection.update(&escaping, &config), None);
+    }
+    assert_eq!(ejection.update(&escaping, &config), Some(1));
+}
+
+#[test]
+fn verlet_conserves_angular_momentum() {
+    let mut config = SimConfig {
+        integrator: Integrator::Verlet,
+        ..SimConfig::default()
+    };
+    config.set_boundary(Boundary::Open);
+    let mut bodies = presets::lagrange_triangle(&config, &mut Rng::new(1));
+    let initial = angular_momentum(&bodies).unwrap();
+    for _ in 0..500 {
+        assert!(step(&mut bodies, &config, config.dt));
+    }
+    let current = angular_momentum(&bodies).unwrap();
+    assert!(
+        (current - initial).abs() <= 1e-3 * initial.abs(),
+        "{current} != {initial}"
+    );
+}
+
+#[test]
+fn potential_matches_potential_energy() {
+    let config = SimConfig::default();
+    let bodies = Body::new_random_bodies(2, &config, &mut Rng::new(7));
+    let (_, potential, _) = total_energy(&bodies, &config);
+    let expected = bodies[1].mass * potential_at(bodies[1].position, &bodies[..1], &config);
+    assert!(
+        (potential - expected).abs() <= 1e-5 * expected.abs(),
+        "{potential} != {expected}"
+    );
+}
+
+#[test]
+fn hierarchical_triple_stays_bound() {
+    let mut config = SimConfig {
+        integrator: Integrator::RK4,
+        ..SimConfig::default()
+    };
+    config.set_boundary(Boundary::Open);
+    let mut bodies = presets::hierarchical_triple(&config, &mut Rng::new(1));
+    for _ in 0..3000 {
+        assert!(step(&mut bodies, &config, config.dt));
+        assert!(!has_collision(&bodies, &config));
+        assert!(ejected_body(&bodies, &config).is_none());
+    }
+}
+
+#[test]
+fn damping_bleeds_energy() {
+    let config = SimConfig {
+        integrator: Integrator::Verlet,
+        ..SimConfig::default()
+    };
+    let damped = SimConfig {
+        damping: 0.01,
+        ..config
+    };
+    let mut rng = Rng::new(5);
+    let mut free = presets::hierarchical_triple(&config, &mut rng);
+    let mut slowed = free.clone();
+    let (_, _, initial) = total_energy(&free, &config);
+    for _ in 0..500 {
+        step(&mut free, &config, config.dt);
+        step(&mut slowed, &damped, damped.dt);
+    }
+    let (_, _, free) = total_energy(&free, &config);
+    let (_, _, slowed) = total_energy(&slowed, &damped);
+    assert!((free - initial).abs() < (slowed - initial).abs() / 10.0);
+    assert!(slowed < initial);
+}
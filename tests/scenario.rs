@@ -0,0 +1,107 @@
+use three_bodies::{
+    rng::Rng,
+    scenario::{from_json, to_json},
+    Body, SimConfig,
+};
+
+/// A scenario of one body, to break in various ways.
+const ONE_BODY: &str = r#"[{"position": {"x": 400, "y": 300}, "velocity": {"x": 0.5, "y": -0.25}, "mass": 5, "radius": 6.8, "colour": {"r": 1, "g": 0.5, "b": 0.25, "a": 1}, "fixed": false}]"#;
+
+#[test]
+fn bodies_survive_a_round_trip() {
+    let config = SimConfig::default();
+    let mut bodies = Body::new_random_bodies(4, &config, &mut Rng::new(17));
+    bodies[2].toggle_fixed();
+    let json = to_json(&bodies).unwrap();
+    // Every field is exported, so loading does not depend on the config or
+    // draw any random numbers.
+    let other = SimConfig {
+        density: 1.0,
+        ..config
+    };
+    let loaded = from_json(&json, &other, &mut Rng::new(1)).unwrap();
+    assert_eq!(loaded.len(), bodies.len());
+    for (body, loaded) in bodies.iter().zip(&loaded) {
+        assert_eq!(body.id, loaded.id);
+        assert_eq!(body.position, loaded.position);
+        assert_eq!(body.velocity, loaded.velocity);
+        assert_eq!(body.mass, loaded.mass);
+        assert_eq!(body.radius, loaded.radius);
+        assert_eq!(body.colour, loaded.colour);
+        assert_eq!(body.fixed, loaded.fixed);
+    }
+}
+
+#[test]
+fn bodies_which_are_not_finite_are_not_exported() {
+    let config = SimConfig::default();
+    let bodies = Body::new_random_bodies(2, &config, &mut Rng::new(17));
+    for number in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let mut broken = bodies.clone();
+        broken[1].velocity.y = number;
+        assert!(to_json(&broken).is_err());
+        let mut broken = bodies.clone();
+        broken[0].colour.g = number;
+        assert!(to_json(&broken).is_err());
+    }
+    // What is exported can always be loaded again.
+    let json = to_json(&bodies).unwrap();
+    assert_eq!(
+        from_json(&json, &config, &mut Rng::new(1)).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn optional_fields_have_defaults() {
+    let config = SimConfig::default();
+    let json = r#"[{"position": {"x": 1, "y": 2}, "velocity": {"x": 0, "y": 0}, "mass": 8}]"#;
+    let bodies = from_json(json, &config, &mut Rng::new(1)).unwrap();
+    assert_eq!(bodies[0].radius, config.radius(8.0));
+    assert!(!bodies[0].fixed);
+}
+
+#[test]
+fn truncated_scenarios_are_rejected() {
+    let config = SimConfig::default();
+    from_json(ONE_BODY, &config, &mut Rng::new(1)).unwrap();
+    for end in 0..ONE_BODY.len() {
+        assert!(
+            from_json(&ONE_BODY[..end], &config, &mut Rng::new(1)).is_err(),
+            "{}",
+            &ONE_BODY[..end]
+        );
+    }
+}
+
+#[test]
+fn malformed_scenarios_are_rejected() {
+    let config = SimConfig::default();
+    let broken = [
+        // Wrong types.
+        ONE_BODY.replace(r#""mass": 5"#, r#""mass": "5""#),
+        ONE_BODY.replace(r#""fixed": false"#, r#""fixed": 0"#),
+        ONE_BODY.replace(r#"{"x": 400, "y": 300}"#, "[400, 300]"),
+        format!("{{\"bodies\": {ONE_BODY}}}"),
+        "[1, 2]".to_string(),
+        // Unknown keys.
+        ONE_BODY.replace(r#""mass""#, r#""weight""#),
+        ONE_BODY.replace(r#""x": 400"#, r#""x": 400, "z": 0"#),
+        ONE_BODY.replace(r#""a": 1"#, r#""alpha": 1"#),
+        // Bad numbers.
+        ONE_BODY.replace(r#""mass": 5"#, r#""mass": 5.0.1"#),
+        ONE_BODY.replace(r#""mass": 5"#, r#""mass": -"#),
+        ONE_BODY.replace(r#""mass": 5"#, r#""mass": 1e999"#),
+        ONE_BODY.replace(r#""mass": 5"#, r#""mass": NaN"#),
+        // Broken syntax.
+        ONE_BODY.replace("}]", "}]]"),
+        ONE_BODY.replace(", \"mass\"", " \"mass\""),
+        ONE_BODY.replace("\"fixed\"", "\"fixed\\q\""),
+    ];
+    for json in &broken {
+        assert!(
+            from_json(json, &config, &mut Rng::new(1)).is_err(),
+            "{json}"
+        );
+    }
+}
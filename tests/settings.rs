@@ -0,0 +1,93 @@
+use three_bodies::{
+    settings, units::Units, Boundary, CollisionMode, Integrator, Palette, SimConfig,
+};
+
+#[test]
+fn settings_override_only_given_defaults() {
+    let settings = settings::parse(
+        r#"
+        # A comment.
+        bodies = 4
+        dt = 0.5  # Trailing comment.
+        integrator = "rk4"
+        boundary = "open"
+        collision_mode = "elastic"
+        "#,
+    )
+    .unwrap();
+    let defaults = SimConfig::default();
+    assert_eq!(settings.bodies, Some(4));
+    assert_eq!(settings.seed, None);
+    assert_eq!(settings.config.dt, 0.5);
+    assert_eq!(settings.config.g, defaults.g);
+    assert!(matches!(settings.config.integrator, Integrator::RK4));
+    assert!(matches!(settings.config.boundary, Boundary::Open));
+    assert!(!settings.config.wrap_gravity);
+    assert!(matches!(
+        settings.config.collision_mode,
+        CollisionMode::Elastic
+    ));
+
+    assert!(settings::parse("g = \"strong\"").is_err());
+    assert!(settings::parse("unknown = 1").is_err());
+}
+
+#[test]
+fn seeds_are_exact() {
+    let settings = settings::parse("seed = 18_446_744_073_709_551_557").unwrap();
+    assert_eq!(settings.seed, Some(18_446_744_073_709_551_557));
+    assert!(settings::parse("seed = 1.5").is_err());
+    assert!(settings::parse("bodies = -3").is_err());
+}
+
+#[test]
+fn units_set_gravity() {
+    let settings = settings::parse("pixels_per_au = 200").unwrap();
+    let units = settings.config.units.unwrap();
+    assert_eq!(units.pixels_per_au, 200.0);
+    assert_eq!(units.steps_per_year, Units::default().steps_per_year);
+    assert_eq!(settings.config.g, units.g());
+
+    // A year's circular orbit at 1 AU around a solar mass is 2 pi AU long.
+    let speed = (units.g() / units.pixels_per_au).sqrt();
+    assert!((units.speed(speed) - std::f32::consts::TAU).abs() < 1e-4);
+}
+
+#[test]
+fn palette_and_background_are_parsed() {
+    let settings = settings::parse(
+        r##"
+        palette = "grayscale"
+        background = "#ff8000"
+        "##,
+    )
+    .unwrap();
+    assert!(matches!(settings.config.palette, Palette::Grayscale));
+    let background = settings.background.unwrap();
+    assert_eq!(
+        (background.r, background.g, background.b),
+        (1.0, 128.0 / 255.0, 0.0)
+    );
+
+    assert!(settings::parse("background = \"#ff80\"").is_err());
+    assert!(settings::parse("palette = \"plaid\"").is_err());
+}
+
+#[test]
+fn trails_which_never_fade_last_as_long_as_there_is_room() {
+    let config = SimConfig {
+        trail_fade: 1.0,
+        ..SimConfig::default()
+    };
+    assert_eq!(config.trail_length(), config.max_trails as u32);
+    assert!(SimConfig::default().trail_length() > 0);
+}
+
+#[test]
+fn trail_fade_is_above_zero_and_at_most_one() {
+    let settings = settings::parse("trail_fade = 1").unwrap();
+    assert_eq!(settings.config.trail_fade, 1.0);
+    assert!(settings::parse("trail_fade = 1.5").is_err());
+    assert!(settings::parse("trail_fade = 0").is_err());
+    assert!(settings::parse("trail_fade = -0.5").is_err());
+}
@@ -0,0 +1,113 @@
+use macroquad::prelude::*;
+use three_bodies::{rng::Rng, Body, Boundary, Palette, SimConfig, World};
+
+/// Returns true if all bodies are within the edges of the world.
+fn in_bounds(world: &World) -> bool {
+    world.bodies.iter().all(|body| {
+        body.position.cmpge(Vec2::ZERO).all() && body.position.cmple(world.config.world).all()
+    })
+}
+
+#[test]
+fn resizing_keeps_bodies_in_bounds() {
+    for boundary in [Boundary::Wrap, Boundary::Bounce] {
+        let mut rng = Rng::new(1);
+        let config = SimConfig {
+            boundary,
+            ..SimConfig::default()
+        };
+        let mut world = World::new(Body::new_random_bodies(10, &config, &mut rng), config);
+        for size in [vec2(400.0, 300.0), vec2(1200.0, 200.0), vec2(300.0, 900.0)] {
+            world.resize(size);
+            assert_eq!(world.config.world, size);
+            assert!(in_bounds(&world));
+            for _ in 0..100 {
+                world.step(1.0);
+            }
+            assert!(in_bounds(&world));
+        }
+    }
+}
+
+#[test]
+fn ids_are_not_reused_after_removal() {
+    let config = SimConfig::default();
+    let mut rng = Rng::new(76);
+    let mut world = World::new(Body::new_random_bodies(3, &config, &mut rng), config);
+    world.remove_body(2, false);
+    let id = world.new_id();
+    assert_eq!(id, 3);
+    assert_ne!(world.new_id(), id);
+
+    // Replaying the scenario does not hand out ids again either.
+    world.reset();
+    assert_eq!(world.new_id(), 5);
+}
+
+#[test]
+fn random_bodies_stay_within_spawn_margin() {
+    let config = SimConfig {
+        spawn_margin: 0.4,
+        ..SimConfig::default()
+    };
+    let bodies = Body::new_random_bodies(100, &config, &mut Rng::new(5));
+    let (min, max) = (config.world * 0.4, config.world * 0.6);
+    assert!(bodies
+        .iter()
+        .all(|body| body.position.cmpge(min).all() && body.position.cmple(max).all()));
+}
+
+#[test]
+fn palettes_do_not_change_scenarios() {
+    let config = SimConfig::default();
+    let bodies = Body::new_random_bodies(3, &config, &mut Rng::new(9));
+    for palette in [
+        Palette::Pastel,
+        Palette::Neon,
+        Palette::Grayscale,
+        Palette::ById,
+    ] {
+        let config = SimConfig { palette, ..config };
+        let recoloured = Body::new_random_bodies(3, &config, &mut Rng::new(9));
+        for (body, recoloured) in bodies.iter().zip(&recoloured) {
+            assert_eq!(body.position, recoloured.position);
+            assert_eq!(body.velocity, recoloured.velocity);
+            assert_eq!(body.mass, recoloured.mass);
+        }
+    }
+}
+
+#[test]
+fn recolouring_matches_generating_with_a_palette() {
+    for palette in [
+        Palette::Random,
+        Palette::Pastel,
+        Palette::Neon,
+        Palette::Grayscale,
+        Palette::ById,
+    ] {
+        let config = SimConfig {
+            palette,
+            ..SimConfig::default()
+        };
+        for body in Body::new_random_bodies(3, &config, &mut Rng::new(9)) {
+            assert_eq!(body.colour, palette.colour(9, body.id));
+        }
+    }
+}
+
+#[test]
+fn colours_by_id_are_the_same_for_every_seed() {
+    let config = SimConfig {
+        palette: Palette::ById,
+        ..SimConfig::default()
+    };
+    let first = Body::new_random_bodies(3, &config, &mut Rng::new(1));
+    let second = Body::new_random_bodies(3, &config, &mut Rng::new(2));
+    for (first, second) in first.iter().zip(&second) {
+        assert_eq!(first.colour, second.colour);
+    }
+    assert_eq!(first[0].colour, RED);
+    assert_eq!(first[1].colour, GREEN);
+    assert_eq!(first[2].colour, BLUE);
+}